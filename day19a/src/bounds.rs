@@ -0,0 +1,146 @@
+use std::error::Error;
+
+use crate::range::{Range, Rating};
+use crate::workflow::{Categories, Entry};
+
+/// Inclusive rating bounds per category, categories without their own bounds use the default.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Bounds {
+    default: (u64, u64),
+    categories: Vec<(String, (u64, u64))>,
+}
+
+impl Default for Bounds {
+    fn default() -> Self {
+        Self {
+            default: (1, 4000),
+            categories: Vec::new(),
+        }
+    }
+}
+
+impl Bounds {
+    /// Parses `x=1..=4000,m=1..70000,*=0..=10`, where `*` replaces the default of all other categories.
+    pub fn parse(spec: &str) -> Result<Self, Box<dyn Error>> {
+        let mut bounds = Self::default();
+        bounds.extend(spec)?;
+        Ok(bounds)
+    }
+
+    /// Reads the bounds from an optional `bounds: <spec>` line in front of the workflows and returns the remaining workflows.
+    pub fn from_header(block: &str) -> Result<(Self, &str), Box<dyn Error>> {
+        let (first, rest) = block.split_once('\n').unwrap_or((block, ""));
+        match first.strip_prefix("bounds:") {
+            Some(spec) => Ok((Self::parse(spec.trim())?, rest)),
+            None => Ok((Self::default(), block)),
+        }
+    }
+
+    /// Looks for `--bounds <spec>` and adds it on top of the current bounds.
+    pub fn apply_args(&mut self, args: &[String]) -> Result<(), Box<dyn Error>> {
+        if let Some(spec) = shared::flag_value::<String>(args, "--bounds")? {
+            self.extend(&spec)?;
+        }
+        Ok(())
+    }
+
+    /// The highest rating allowed in any category, which decides the type the box is stored in.
+    pub fn max(&self) -> u64 {
+        self.categories.iter()
+            .map(|(_, (_, end))| *end)
+            .fold(self.default.1, u64::max)
+    }
+
+    /// The box of all parts within the bounds, stored in `T`.
+    pub fn entry<T: Rating>(&self, categories: &Categories) -> Result<Entry<Range<T>>, Box<dyn Error>> {
+        if let Some((name, _)) = self.categories.iter().find(|(name, _)| categories.get(name).is_none()) {
+            return Err(format!("Bounds given for unknown category '{}'", name).into());
+        }
+        let values = categories.iter()
+            .map(|category| {
+                let name = categories.name(category);
+                let (start, end) = self.categories.iter()
+                    .rev()
+                    .find(|(known, _)| known == name)
+                    .map(|(_, bounds)| *bounds)
+                    .unwrap_or(self.default);
+                Range::new(start, end)
+                    .ok_or_else(|| format!("Bounds {}..={} of '{}' do not fit into {}", start, end, name, std::any::type_name::<T>()).into())
+            })
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+        Ok(Entry::new(values))
+    }
+
+    fn extend(&mut self, spec: &str) -> Result<(), Box<dyn Error>> {
+        for bound in spec.split(',').map(str::trim).filter(|bound| !bound.is_empty()) {
+            let (name, range) = bound.split_once('=')
+                .ok_or_else(|| format!("Expected <category>=<start>..<end>, found '{}'", bound))?;
+            let range = parse_range(range)?;
+            match name.trim() {
+                "*" => self.default = range,
+                name => self.categories.push((name.to_string(), range)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses `start..=end` or the exclusive `start..end` into inclusive bounds.
+fn parse_range(range: &str) -> Result<(u64, u64), Box<dyn Error>> {
+    let (start, end) = range.split_once("..")
+        .ok_or_else(|| format!("Expected <start>..<end>, found '{}'", range))?;
+    let start = start.trim().parse::<u64>()?;
+    let end = match end.strip_prefix('=') {
+        Some(end) => end.trim().parse::<u64>()?,
+        None => end.trim().parse::<u64>()?.checked_sub(1).ok_or("Found empty bounds")?,
+    };
+    if start > end {
+        return Err(format!("Found empty bounds {}", range).into());
+    }
+    Ok((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn categories(names: &[&str]) -> Categories {
+        let mut categories = Categories::default();
+        for name in names {
+            categories.intern(name);
+        }
+        categories
+    }
+
+    #[test]
+    fn test_default_bounds() {
+        let entry = Bounds::default().entry::<u16>(&categories(&["x", "m"])).unwrap();
+
+        assert_eq!(entry, Entry::new(vec![Range { start: 1, end: 4000 }; 2]));
+    }
+
+    #[test]
+    fn test_header_and_args() {
+        let (mut bounds, rest) = Bounds::from_header("bounds: *=0..=9,m=1..70001\nin{x>1:A,R}").unwrap();
+        bounds.apply_args(&["--bounds".to_string(), "x=5..=6".to_string()]).unwrap();
+
+        assert_eq!(rest, "in{x>1:A,R}");
+        assert_eq!(bounds.max(), 70000);
+        assert_eq!(bounds.entry::<u32>(&categories(&["x", "m", "a"])).unwrap(), Entry::new(vec![
+            Range { start: 5, end: 6 },
+            Range { start: 1, end: 70000 },
+            Range { start: 0, end: 9 },
+        ]));
+    }
+
+    #[test]
+    fn test_bound_errors() {
+        let bounds = Bounds::parse("m=1..=70000").unwrap();
+
+        assert_eq!(bounds.entry::<u16>(&categories(&["m"])).unwrap_err().to_string(), "Bounds 1..=70000 of 'm' do not fit into u16");
+        assert_eq!(bounds.entry::<u32>(&categories(&["x"])).unwrap_err().to_string(), "Bounds given for unknown category 'm'");
+        assert!(Bounds::parse("x=5..5").is_err());
+        assert!(Bounds::parse("x").is_err());
+        assert_eq!(Bounds::from_header("in{A}").unwrap(), (Bounds::default(), "in{A}"));
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use crate::workflow::{Category, Condition, Destination, Entry, Expression, Workflow};
+
+/// Workflows lowered into a flat decision tree, evaluating a part is a loop over node indices without any lookups by name.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Program {
+    nodes: Vec<Node>,
+    start: Target,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+struct Node {
+    category: Category,
+    condition: Condition,
+    value: u64,
+    then: Target,
+    otherwise: Target,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Target {
+    Accepted,
+    Rejected,
+    Node(u32),
+}
+
+struct Compiler<'a> {
+    workflows: &'a HashMap<&'a str, Workflow>,
+    /// Interned workflow names, each pointing to the first node of its compiled rules.
+    compiled: HashMap<&'a str, Target>,
+    nodes: Vec<Node>,
+}
+
+impl Program {
+    /// Compiles all workflows reachable from `start`, which have to be acyclic as checked by `validate::check`.
+    pub fn compile(workflows: &HashMap<&str, Workflow>, start: &str) -> Self {
+        let mut compiler = Compiler {
+            workflows,
+            compiled: HashMap::new(),
+            nodes: Vec::new(),
+        };
+        let start = compiler.workflow(start);
+        Self {
+            nodes: compiler.nodes,
+            start,
+        }
+    }
+
+    pub fn accepts(&self, entry: &Entry<u64>) -> bool {
+        let mut target = self.start;
+        loop {
+            match target {
+                Target::Accepted => return true,
+                Target::Rejected => return false,
+                Target::Node(idx) => {
+                    let node = &self.nodes[idx as usize];
+                    target = if node.condition.holds(entry[node.category], node.value) {
+                        node.then
+                    } else {
+                        node.otherwise
+                    };
+                }
+            }
+        }
+    }
+
+    /// Number of comparisons in the decision tree.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl<'a> Compiler<'a> {
+    fn workflow(&mut self, name: &str) -> Target {
+        if let Some(target) = self.compiled.get(name) {
+            return *target;
+        }
+        let (name, workflow) = self.workflows.get_key_value(name).expect("Could not find reference");
+        let mut target = self.destination(&workflow.default);
+        for rule in workflow.rules.iter().rev() {
+            let then = self.destination(&rule.destination);
+            target = self.expression(&rule.expression, then, target);
+        }
+        self.compiled.insert(name, target);
+        target
+    }
+
+    fn destination(&mut self, destination: &Destination) -> Target {
+        match destination {
+            Destination::Accepted => Target::Accepted,
+            Destination::Rejected => Target::Rejected,
+            Destination::Ref(name) => self.workflow(name),
+        }
+    }
+
+    /// Lowers an expression with short-circuiting, every sub-expression is compiled exactly once.
+    fn expression(&mut self, expression: &Expression, then: Target, otherwise: Target) -> Target {
+        match expression {
+            Expression::Compare(comparison) => {
+                self.nodes.push(Node {
+                    category: comparison.category,
+                    condition: comparison.condition,
+                    value: comparison.value,
+                    then,
+                    otherwise,
+                });
+                Target::Node(self.nodes.len() as u32 - 1)
+            }
+            Expression::And(left, right) => {
+                let right = self.expression(right, then, otherwise);
+                self.expression(left, right, otherwise)
+            }
+            Expression::Or(left, right) => {
+                let right = self.expression(right, then, otherwise);
+                self.expression(left, then, right)
+            }
+            Expression::Not(expression) => self.expression(expression, otherwise, then),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{parse_entries, parse_workflows};
+    use crate::workflow::{resolve_destination, Categories};
+
+    use super::*;
+
+    #[test]
+    fn test_compile_example() {
+        let file = std::fs::read_to_string("example.txt").unwrap();
+        let (block, entries) = file.split_once("\n\n").unwrap();
+        let mut categories = Categories::default();
+        let workflows = parse_workflows(block, &mut categories);
+        let entries = parse_entries(entries, &mut categories);
+        let program = Program::compile(&workflows, "in");
+
+        assert_eq!(program.len(), workflows.values().map(|workflow| workflow.rules.len()).sum::<usize>());
+        let accepted: u64 = entries.iter()
+            .filter(|entry| program.accepts(entry))
+            .map(|entry| entry.sum())
+            .sum();
+        assert_eq!(accepted, 19114);
+    }
+
+    #[test]
+    fn test_compile_compound() {
+        let mut categories = Categories::default();
+        let workflows = parse_workflows("in{x>10&&(m<200||a==1):px,!(s!=2&&x in 1..5):R,A}\npx{m>100:A,R}", &mut categories);
+        let program = Program::compile(&workflows, "in");
+        let start = Destination::Ref("in".to_string());
+
+        for values in itertools::iproduct!([0, 3, 11], [50, 150, 250], [0, 1], [1, 2]) {
+            let entry = Entry::new(vec![values.0, values.1, values.2, values.3]);
+            assert_eq!(program.accepts(&entry), resolve_destination(&entry, &start, &workflows), "{:?}", entry);
+        }
+    }
+}
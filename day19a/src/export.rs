@@ -0,0 +1,187 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::Write;
+
+use crate::parser::parse_entry;
+use crate::workflow::{Categories, Destination, Entry, Workflow};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Format {
+    Dot,
+    Mermaid,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ExportOptions {
+    pub format: Format,
+    /// Part in puzzle syntax whose path through the workflows gets highlighted.
+    pub part: Option<String>,
+}
+
+/// An edge of the graph: the workflow, the index of its rule or `None` for the default, and the destination.
+type Edge<'a> = (&'a str, Option<usize>, &'a Destination);
+
+/// Renders every rule as a labeled edge, with `in` first and the rest sorted by name.
+pub fn export(workflows: &HashMap<&str, Workflow>, categories: &Categories, format: Format, part: Option<&Entry<u64>>) -> String {
+    let mut names: Vec<&str> = workflows.keys().copied().collect();
+    names.sort_by_key(|name| (*name != "in", *name));
+    let edges: Vec<Edge> = names.iter()
+        .flat_map(|name| {
+            let workflow = &workflows[name];
+            workflow.rules.iter()
+                .enumerate()
+                .map(|(idx, rule)| (*name, Some(idx), &rule.destination))
+                .chain([(*name, None, &workflow.default)])
+        })
+        .collect();
+    let path = part.map(|part| find_path(part, workflows)).unwrap_or_default();
+    let label = |(name, rule, _): &Edge| match rule {
+        Some(idx) => workflows[name].rules[*idx].expression.display(categories).to_string(),
+        None => "else".to_string(),
+    };
+    match format {
+        Format::Dot => export_dot(&names, &edges, &path, label),
+        Format::Mermaid => export_mermaid(&names, &edges, &path, label),
+    }
+}
+
+/// The edges a part takes through `Workflow::evaluate`, starting at `in`.
+fn find_path<'a>(part: &Entry<u64>, workflows: &'a HashMap<&str, Workflow>) -> HashSet<(&'a str, Option<usize>)> {
+    let mut path = HashSet::new();
+    let mut current = workflows.get_key_value("in");
+    while let Some((name, workflow)) = current {
+        let rule = workflow.rules.iter().position(|rule| rule.expression.holds(part));
+        path.insert((*name, rule));
+        current = match workflow.evaluate(part) {
+            Destination::Ref(next) => workflows.get_key_value(next.as_str()),
+            _ => None,
+        };
+    }
+    path
+}
+
+fn export_dot(names: &[&str], edges: &[Edge], path: &HashSet<(&str, Option<usize>)>, label: impl Fn(&Edge) -> String) -> String {
+    let mut output = String::from("digraph workflows {\n");
+    output.push_str("    \"A\" [shape=doublecircle, style=filled, fillcolor=palegreen];\n");
+    output.push_str("    \"R\" [shape=doublecircle, style=filled, fillcolor=lightcoral];\n");
+    for name in names {
+        writeln!(output, "    \"{}\" [shape=box];", name).unwrap();
+    }
+    for edge @ (name, rule, destination) in edges {
+        let style = if path.contains(&(*name, *rule)) { ", color=red, penwidth=2" } else { "" };
+        writeln!(output, "    \"{}\" -> \"{}\" [label=\"{}\"{}];", name, destination, label(edge), style).unwrap();
+    }
+    output.push('}');
+    output
+}
+
+fn export_mermaid(names: &[&str], edges: &[Edge], path: &HashSet<(&str, Option<usize>)>, label: impl Fn(&Edge) -> String) -> String {
+    let id = |destination: &Destination| match destination {
+        Destination::Ref(name) => shared::mermaid_id("w", name),
+        destination => destination.to_string(),
+    };
+    let mut output = String::from("flowchart LR\n");
+    output.push_str("    A((A)):::accepted\n");
+    output.push_str("    R((R)):::rejected\n");
+    for name in names {
+        writeln!(output, "    {}[\"{}\"]", shared::mermaid_id("w", name), name).unwrap();
+    }
+    for edge @ (name, _, destination) in edges {
+        writeln!(output, "    {} -- \"{}\" --> {}", shared::mermaid_id("w", name), label(edge), id(destination)).unwrap();
+    }
+    output.push_str("    classDef accepted fill:#9f9,stroke:#393\n");
+    output.push_str("    classDef rejected fill:#f99,stroke:#933");
+    let highlighted: Vec<String> = edges.iter()
+        .enumerate()
+        .filter(|(_, (name, rule, _))| path.contains(&(*name, *rule)))
+        .map(|(idx, _)| idx.to_string())
+        .collect();
+    if !highlighted.is_empty() {
+        write!(output, "\n    linkStyle {} stroke:red,stroke-width:3px", highlighted.join(",")).unwrap();
+    }
+    output
+}
+
+impl ExportOptions {
+    /// Reads `--export dot|mermaid` and an optional `--part {x=..,m=..}` from the command line arguments.
+    pub fn from_args(args: &[String]) -> Result<Option<Self>, Box<dyn Error>> {
+        let format = match shared::flag_argument(args, "--export") {
+            None => return Ok(None),
+            Some(Some("dot")) => Format::Dot,
+            Some(Some("mermaid")) => Format::Mermaid,
+            Some(_) => return Err("Expected dot or mermaid after --export".into()),
+        };
+        Ok(Some(Self { format, part: shared::flag_value(args, "--part")? }))
+    }
+
+    pub fn render(&self, workflows: &HashMap<&str, Workflow>, categories: &mut Categories) -> Result<String, Box<dyn Error>> {
+        let part = match &self.part {
+            Some(part) => Some(parse_entry(part, categories)
+                .map_err(|err| format!("Could not parse part {}: {}", part, err))?.1),
+            None => None,
+        };
+        Ok(export(workflows, categories, self.format, part.as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_workflows;
+
+    use super::*;
+
+    const BLOCK: &str = "in{s<1351:px,R}\npx{a<2006&&m>1:A,R}";
+
+    #[test]
+    fn test_export_dot() {
+        let mut categories = Categories::default();
+        let workflows = parse_workflows(BLOCK, &mut categories);
+        let part = Entry::new(vec![1000, 3000, 1]);
+
+        assert_eq!(export(&workflows, &categories, Format::Dot, Some(&part)), [
+            "digraph workflows {",
+            "    \"A\" [shape=doublecircle, style=filled, fillcolor=palegreen];",
+            "    \"R\" [shape=doublecircle, style=filled, fillcolor=lightcoral];",
+            "    \"in\" [shape=box];",
+            "    \"px\" [shape=box];",
+            "    \"in\" -> \"px\" [label=\"s<1351\", color=red, penwidth=2];",
+            "    \"in\" -> \"R\" [label=\"else\"];",
+            "    \"px\" -> \"A\" [label=\"a<2006&&m>1\"];",
+            "    \"px\" -> \"R\" [label=\"else\", color=red, penwidth=2];",
+            "}",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_export_mermaid() {
+        let mut categories = Categories::default();
+        let workflows = parse_workflows(BLOCK, &mut categories);
+
+        assert_eq!(export(&workflows, &categories, Format::Mermaid, None), [
+            "flowchart LR",
+            "    A((A)):::accepted",
+            "    R((R)):::rejected",
+            "    w_in[\"in\"]",
+            "    w_px[\"px\"]",
+            "    w_in -- \"s<1351\" --> w_px",
+            "    w_in -- \"else\" --> R",
+            "    w_px -- \"a<2006&&m>1\" --> A",
+            "    w_px -- \"else\" --> R",
+            "    classDef accepted fill:#9f9,stroke:#393",
+            "    classDef rejected fill:#f99,stroke:#933",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_options_from_args() {
+        let args: Vec<String> = ["--export", "mermaid", "--part", "{s=1,a=1,m=2}"].map(String::from).to_vec();
+        let options = ExportOptions::from_args(&args).unwrap().unwrap();
+        let mut categories = Categories::default();
+        let workflows = parse_workflows(BLOCK, &mut categories);
+
+        assert_eq!(options, ExportOptions { format: Format::Mermaid, part: Some("{s=1,a=1,m=2}".to_string()) });
+        assert!(options.render(&workflows, &mut categories).unwrap().ends_with("linkStyle 0,2 stroke:red,stroke-width:3px"));
+        assert_eq!(ExportOptions::from_args(&[]).unwrap(), None);
+        assert!(ExportOptions::from_args(&["--export".to_string(), "svg".to_string()]).is_err());
+    }
+}
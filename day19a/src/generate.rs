@@ -0,0 +1,242 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::workflow::{Categories, Category, Condition, Destination, Entry, Expression, Rule, Workflow};
+
+/// Seeded generator of well-formed puzzle inputs: every workflow graph is acyclic and all references resolve.
+pub struct Generator {
+    /// Longest chain of workflows below `in`.
+    pub depth: usize,
+    /// Most rules per workflow.
+    pub fan_out: usize,
+    /// Ratings and rule values are drawn from `1..=max_value`.
+    pub max_value: u64,
+    rng: StdRng,
+    workflows: Vec<(String, Workflow)>,
+    /// Longest chain of workflows below each generated workflow, in the same order.
+    heights: Vec<usize>,
+    names: usize,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            depth: 4,
+            fan_out: 3,
+            max_value: 4000,
+            rng: StdRng::seed_from_u64(seed),
+            workflows: Vec::new(),
+            heights: Vec::new(),
+            names: 0,
+        }
+    }
+
+    /// Generates a workflow graph starting at `in`, which is always the first workflow returned.
+    pub fn workflows(&mut self, categories: &Categories) -> Vec<(String, Workflow)> {
+        self.workflows.clear();
+        self.heights.clear();
+        self.names = 0;
+        self.workflow("in".to_string(), 0, categories);
+        self.workflows.reverse();
+        std::mem::take(&mut self.workflows)
+    }
+
+    pub fn parts(&mut self, categories: &Categories, count: usize) -> Vec<Entry<u64>> {
+        (0..count)
+            .map(|_| Entry::new(categories.iter()
+                .map(|_| self.rng.gen_range(1..=self.max_value))
+                .collect()))
+            .collect()
+    }
+
+    /// A complete puzzle input with workflows and parts.
+    pub fn input(&mut self, categories: &Categories, parts: usize) -> String {
+        let workflows: Vec<String> = self.workflows(categories).iter()
+            .map(|(name, workflow)| workflow.display(name, categories).to_string())
+            .collect();
+        let parts: Vec<String> = self.parts(categories, parts).iter()
+            .map(|part| part.display(categories).to_string())
+            .collect();
+        format!("{}\n\n{}", workflows.join("\n"), parts.join("\n"))
+    }
+
+    /// Workflows are only finished after all of their children, so referencing a finished one can never close a cycle.
+    fn workflow(&mut self, name: String, depth: usize, categories: &Categories) -> usize {
+        let mut height = 0;
+        let rules = (0..self.rng.gen_range(0..=self.fan_out))
+            .map(|_| {
+                let expression = self.expression(2, categories);
+                let (destination, below) = self.destination(depth, categories);
+                height = height.max(below);
+                Rule { expression, destination }
+            })
+            .collect();
+        let (default, below) = self.destination(depth, categories);
+        self.workflows.push((name, Workflow { rules, default }));
+        self.heights.push(height.max(below));
+        height.max(below)
+    }
+
+    /// A destination for a workflow at `depth`, together with the longest chain of workflows it starts.
+    fn destination(&mut self, depth: usize, categories: &Categories) -> (Destination, usize) {
+        if depth < self.depth && self.rng.gen_bool(0.5) {
+            let finished: Vec<usize> = (0..self.workflows.len())
+                .filter(|idx| depth + 1 + self.heights[*idx] <= self.depth)
+                .collect();
+            if !finished.is_empty() && self.rng.gen_bool(0.2) {
+                let idx = finished[self.rng.gen_range(0..finished.len())];
+                return (Destination::Ref(self.workflows[idx].0.clone()), self.heights[idx] + 1);
+            }
+            let name = format!("w{}", letters(self.names));
+            self.names += 1;
+            let height = self.workflow(name.clone(), depth + 1, categories);
+            return (Destination::Ref(name), height + 1);
+        }
+        if self.rng.gen_bool(0.5) {
+            (Destination::Accepted, 0)
+        } else {
+            (Destination::Rejected, 0)
+        }
+    }
+
+    fn expression(&mut self, depth: usize, categories: &Categories) -> Expression {
+        if depth == 0 || self.rng.gen_bool(0.6) {
+            let category = Category(self.rng.gen_range(0..categories.len()));
+            let value = self.rng.gen_range(1..=self.max_value);
+            let condition = match self.rng.gen_range(0..7) {
+                0 => Condition::Larger,
+                1 => Condition::Lower,
+                2 => Condition::LargerOrEqual,
+                3 => Condition::LowerOrEqual,
+                4 => Condition::Equal,
+                5 => Condition::NotEqual,
+                _ => Condition::In(self.rng.gen_range(value..=self.max_value + 1)),
+            };
+            return Expression::compare(category, condition, value);
+        }
+        match self.rng.gen_range(0..3) {
+            0 => Expression::And(Box::new(self.expression(depth - 1, categories)), Box::new(self.expression(depth - 1, categories))),
+            1 => Expression::Or(Box::new(self.expression(depth - 1, categories)), Box::new(self.expression(depth - 1, categories))),
+            _ => Expression::Not(Box::new(self.expression(depth - 1, categories))),
+        }
+    }
+}
+
+/// Spells a number with lowercase letters, since workflow names have to be alphabetic.
+fn letters(mut idx: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'a' + (idx % 26) as u8);
+        idx /= 26;
+        if idx == 0 {
+            break;
+        }
+    }
+    String::from_utf8(name).expect("Could not spell name")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use itertools::Itertools;
+
+    use crate::compile::Program;
+    use crate::parser::{parse_entries, parse_workflows};
+    use crate::range::Range;
+    use crate::region::Region;
+    use crate::validate::validate;
+    use crate::workflow::resolve_destination;
+
+    use super::*;
+
+    fn categories() -> Categories {
+        let mut categories = Categories::default();
+        for name in ["x", "m", "a"] {
+            categories.intern(name);
+        }
+        categories
+    }
+
+    #[test]
+    fn test_generated_workflows_are_valid() {
+        let categories = categories();
+        for seed in 0..50 {
+            let generated = Generator::new(seed).workflows(&categories);
+            let workflows: HashMap<&str, Workflow> = generated.iter()
+                .map(|(name, workflow)| (name.as_str(), workflow.clone()))
+                .collect();
+
+            assert_eq!(generated[0].0, "in");
+            assert_eq!(workflows.len(), generated.len());
+            assert!(validate(&workflows, &categories).iter().all(|finding| !finding.is_error()), "seed {}", seed);
+        }
+    }
+
+    fn height(name: &str, workflows: &HashMap<&str, Workflow>) -> usize {
+        workflows[name].references()
+            .into_iter()
+            .map(|reference| height(reference, workflows) + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn test_depth_is_respected() {
+        let categories = categories();
+        for seed in 0..500 {
+            let mut generator = Generator::new(seed);
+            generator.depth = 3;
+            let generated = generator.workflows(&categories);
+            let workflows: HashMap<&str, Workflow> = generated.iter()
+                .map(|(name, workflow)| (name.as_str(), workflow.clone()))
+                .collect();
+
+            assert!(height("in", &workflows) <= 3, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_concrete_matches_intervals() {
+        let categories = categories();
+        let start = Destination::Ref("in".to_string());
+        for seed in 0..50 {
+            let mut generator = Generator::new(seed);
+            generator.max_value = 8;
+            let generated = generator.workflows(&categories);
+            let workflows: HashMap<&str, Workflow> = generated.iter()
+                .map(|(name, workflow)| (name.as_str(), workflow.clone()))
+                .collect();
+            let program = Program::compile(&workflows, "in");
+
+            let grid = (0..categories.len()).map(|_| 1..=8u64).multi_cartesian_product();
+            let accepted = grid.map(Entry::new)
+                .filter(|part| {
+                    let accepted = resolve_destination(part, &start, &workflows);
+                    assert_eq!(program.accepts(part), accepted, "seed {}", seed);
+                    accepted
+                })
+                .count() as u128;
+            let region = Region::accepted(&workflows, "in", Entry::filled(&categories, Range { start: 1u8, end: 8 }));
+            assert_eq!(region.volume(), Some(accepted), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_generated_input_parses() {
+        let mut categories = categories();
+        let mut generator = Generator::new(7);
+        let input = generator.input(&categories, 20);
+        let (block, parts) = input.split_once("\n\n").unwrap();
+        let workflows = parse_workflows(block, &mut categories);
+        let parts = parse_entries(parts, &mut categories);
+
+        assert_eq!(categories.len(), 3);
+        assert_eq!(parts.len(), 20);
+        let region = Region::accepted(&workflows, "in", Entry::filled(&categories, Range { start: 1u16, end: 4000 }));
+        let start = Destination::Ref("in".to_string());
+        for part in &parts {
+            assert_eq!(region.contains(part), resolve_destination(part, &start, &workflows));
+        }
+    }
+}
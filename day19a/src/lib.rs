@@ -0,0 +1,11 @@
+pub mod bounds;
+pub mod compile;
+pub mod export;
+pub mod generate;
+pub mod optimize;
+pub mod parser;
+pub mod printer;
+pub mod range;
+pub mod region;
+pub mod validate;
+pub mod workflow;
@@ -1,52 +1,128 @@
-#![feature(slice_take)]
+#![feature(test)]
+extern crate test;
 
-use std::collections::HashMap;
 use std::error::Error;
 
-use nom::InputTake;
-use tokio_stream::StreamExt;
+use itertools::Itertools;
 
-use crate::parser::{parse_entry, parse_workspace};
-use crate::types::{Destination, Entry, Workflow};
-
-mod parser;
-mod types;
+use day19a::{optimize, printer, validate};
+use day19a::compile::Program;
+use day19a::export::ExportOptions;
+use day19a::parser::{parse_entries, parse_workflows};
+use day19a::workflow::Categories;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if shared::has_flag(&args, "--validate") {
+        let file = std::fs::read_to_string("day19a/input.txt")?;
+        let (block, _) = file.split_once("\n\n").expect("Could not find workspace part");
+        println!("{}", report(block));
+        return Ok(());
+    }
+    let export = ExportOptions::from_args(&args)?;
+    if export.is_some() || shared::has_flag(&args, "--optimize") {
+        let file = std::fs::read_to_string("day19a/input.txt")?;
+        let (block, _) = file.split_once("\n\n").expect("Could not find workspace part");
+        let mut categories = Categories::default();
+        let workflows = parse_workflows(block, &mut categories);
+        validate::check(&workflows, &categories)?;
+        match export {
+            Some(export) => println!("{}", export.render(&workflows, &mut categories)?),
+            None => println!("{}", printer::print_workflows(&optimize::optimize(workflows, &categories), &categories)),
+        }
+        return Ok(());
+    }
     shared::execute_solution("day19a/input.txt", solution).await
 }
 
+/// Every finding of the validator, one per line, warnings included.
+fn report(block: &str) -> String {
+    let mut categories = Categories::default();
+    let workflows = parse_workflows(block, &mut categories);
+    validate::validate(&workflows, &categories).iter().join("\n")
+}
+
 async fn solution(file: String) -> Result<u64, Box<dyn Error>> {
-    let parts: Vec<String> = file.split("\n\n").map(String::from).collect();
-    let workflows: HashMap<&str, Workflow> = parts.get(0)
-        .expect("Could not find workspace part")
-        .split("\n")
-        .map(|line| parse_workspace(line).expect("Could not parse workspace"))
-        .collect();
-
-    let entries: Vec<Entry> = parts.get(1)
-        .expect("Could not find entries")
-        .split("\n")
-        .map(|line| parse_entry(line).expect("Could not parse entry").1)
-        .collect();
+    let parts: Vec<&str> = file.split("\n\n").collect();
+    let mut categories = Categories::default();
+    let workflows = parse_workflows(parts.first().expect("Could not find workspace part"), &mut categories);
+    let entries = parse_entries(parts.get(1).expect("Could not find entries"), &mut categories);
+    validate::check(&workflows, &categories)?;
+    let program = Program::compile(&workflows, "in");
 
     Ok(entries.iter()
-        .map(|entry| (resolve_destination(entry, &Destination::Ref("in".to_string()), &workflows), entry))
-        .filter(|(result, entry)| *result)
-        .map(|(result, entry)| entry.sum())
+        .filter(|entry| program.accepts(entry))
+        .map(|entry| entry.sum())
         .sum())
 }
 
-fn resolve_destination(entry: &Entry, destination: &Destination, workflows: &HashMap<&str, Workflow>) -> bool {
-    let mut destination = destination;
-    while let Destination::Ref(name) = destination {
-        destination = workflows.get(name.as_str()).expect("Could not find reference").evaluate(entry);
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use test::Bencher;
+
+    use day19a::generate::Generator;
+    use day19a::workflow::{resolve_destination, Destination, Entry, Workflow};
+
+    use super::*;
+
+    fn load_workflows<'a>(content: &'a str, categories: &mut Categories) -> (HashMap<&'a str, Workflow>, Vec<Entry<u64>>) {
+        let (block, _) = content.split_once("\n\n").unwrap();
+        let workflows = parse_workflows(block, categories);
+        let parts = Generator::new(19).parts(categories, 10_000);
+        (workflows, parts)
     }
-    match destination {
-        Destination::Accepted => true,
-        Destination::Rejected => false,
-        Destination::Ref(_) => panic!("Something went terribly wrong")
+
+    #[test]
+    fn test_compiled_matches_resolve() {
+        let content = std::fs::read_to_string("input.txt").unwrap();
+        let mut categories = Categories::default();
+        let (workflows, parts) = load_workflows(&content, &mut categories);
+        let program = Program::compile(&workflows, "in");
+        let start = Destination::Ref("in".to_string());
+
+        for part in &parts {
+            assert_eq!(program.accepts(part), resolve_destination(part, &start, &workflows));
+        }
     }
-}
 
+    #[bench]
+    fn bench_resolve_destination(b: &mut Bencher) {
+        let content = std::fs::read_to_string("input.txt").unwrap();
+        let mut categories = Categories::default();
+        let (workflows, parts) = load_workflows(&content, &mut categories);
+        let start = Destination::Ref("in".to_string());
+
+        b.iter(|| parts.iter().filter(|part| resolve_destination(part, &start, &workflows)).count());
+    }
+
+    #[bench]
+    fn bench_compiled(b: &mut Bencher) {
+        let content = std::fs::read_to_string("input.txt").unwrap();
+        let mut categories = Categories::default();
+        let (workflows, parts) = load_workflows(&content, &mut categories);
+        let program = Program::compile(&workflows, "in");
+
+        b.iter(|| parts.iter().filter(|part| program.accepts(part)).count());
+    }
+
+    #[tokio::test]
+    async fn test_report_warnings() -> Result<(), Box<dyn Error>> {
+        let block = "in{x>10:A,x>20:R,R}\nlost{A}";
+        let result = solution(format!("{}\n\n{{x=30,m=1,a=1,s=1}}", block)).await?;
+
+        assert_eq!(result, 33);
+        assert_eq!(report(block), "Workflow 'lost' is never reached from 'in'\nRule 1 of workflow 'in' can never fire");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_solution() -> Result<(), Box<dyn Error>> {
+        let lines = std::fs::read_to_string("example.txt")?;
+        let result = solution(lines).await?;
+        assert_eq!(result, 19114);
+        Ok(())
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use crate::validate::{find_reachable, reachable_rules};
+use crate::workflow::{Categories, Destination, Expression, Rule, Workflow};
+
+/// Rewrites the workflows into an equivalent, smaller ruleset until no optimization applies anymore.
+pub fn optimize<'a>(mut workflows: HashMap<&'a str, Workflow>, categories: &Categories) -> HashMap<&'a str, Workflow> {
+    loop {
+        let mut changed = false;
+        for workflow in workflows.values_mut() {
+            changed |= remove_redundant_rules(workflow, categories);
+        }
+        changed |= collapse_workflows(&mut workflows);
+        changed |= inline_single_use(&mut workflows);
+        changed |= prune_unreachable(&mut workflows);
+        if !changed {
+            return workflows;
+        }
+    }
+}
+
+/// Drops rules that can never fire and trailing rules that lead to the default anyway.
+fn remove_redundant_rules(workflow: &mut Workflow, categories: &Categories) -> bool {
+    let before = workflow.clone();
+    let (reachable, default_reachable) = reachable_rules(workflow, categories);
+    let mut reachable = reachable.into_iter();
+    workflow.rules.retain(|_| reachable.next().unwrap_or(true));
+    if !default_reachable {
+        if let Some(last) = workflow.rules.pop() {
+            workflow.default = last.destination;
+        }
+    }
+    while workflow.rules.last().is_some_and(|rule| rule.destination == workflow.default) {
+        workflow.rules.pop();
+    }
+    *workflow != before
+}
+
+/// Replaces every reference to a workflow without rules by its default.
+fn collapse_workflows(workflows: &mut HashMap<&str, Workflow>) -> bool {
+    let collapsible = workflows.iter()
+        .filter(|(name, workflow)| **name != "in" && workflow.rules.is_empty() && workflow.default != Destination::Ref(name.to_string()))
+        .map(|(name, workflow)| (*name, workflow.default.clone()))
+        .next();
+    let Some((name, default)) = collapsible else {
+        return false;
+    };
+    workflows.remove(name);
+    for workflow in workflows.values_mut() {
+        for destination in workflow.destinations_mut() {
+            if *destination == Destination::Ref(name.to_string()) {
+                *destination = default.clone();
+            }
+        }
+    }
+    true
+}
+
+/// Merges a workflow referenced exactly once into the workflow referencing it. Behind a rule the condition is repeated
+/// for every inlined rule, so only workflows with at most one rule are inlined there to keep the ruleset from growing.
+fn inline_single_use(workflows: &mut HashMap<&str, Workflow>) -> bool {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for workflow in workflows.values() {
+        for reference in workflow.references() {
+            *counts.entry(reference).or_default() += 1;
+        }
+    }
+    let candidate = counts.iter()
+        .filter(|(name, count)| **count == 1 && **name != "in" && workflows.contains_key(**name))
+        .map(|(name, _)| name.to_string())
+        .find_map(|name| workflows.iter()
+            .find(|(parent, workflow)| **parent != name && is_inlinable(workflow, &workflows[name.as_str()], &name))
+            .map(|(parent, _)| (parent.to_string(), name)));
+    let Some((parent, name)) = candidate else {
+        return false;
+    };
+    let inlined = workflows.remove(name.as_str()).expect("Could not find inlined workflow");
+    let parent = workflows.get_mut(parent.as_str()).expect("Could not find parent workflow");
+    let reference = Destination::Ref(name);
+    if parent.default == reference {
+        parent.rules.extend(inlined.rules);
+        parent.default = inlined.default;
+        return true;
+    }
+    let idx = parent.rules.iter()
+        .position(|rule| rule.destination == reference)
+        .expect("Could not find referencing rule");
+    let condition = parent.rules[idx].expression.clone();
+    let mut rules: Vec<Rule> = inlined.rules.into_iter()
+        .map(|rule| Rule {
+            expression: Expression::And(Box::new(condition.clone()), Box::new(rule.expression)),
+            destination: rule.destination,
+        })
+        .collect();
+    rules.push(Rule {
+        expression: condition,
+        destination: inlined.default,
+    });
+    parent.rules.splice(idx..=idx, rules);
+    true
+}
+
+fn is_inlinable(parent: &Workflow, inlined: &Workflow, name: &str) -> bool {
+    let reference = Destination::Ref(name.to_string());
+    parent.default == reference || (inlined.rules.len() <= 1 && parent.rules.iter().any(|rule| rule.destination == reference))
+}
+
+fn prune_unreachable(workflows: &mut HashMap<&str, Workflow>) -> bool {
+    if !workflows.contains_key("in") {
+        return false;
+    }
+    let reachable: Vec<String> = find_reachable("in", workflows).into_iter()
+        .map(String::from)
+        .collect();
+    let before = workflows.len();
+    workflows.retain(|name, _| reachable.iter().any(|reachable| reachable == name));
+    workflows.len() != before
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{parse_entries, parse_workflows};
+    use crate::printer::print_workflows;
+    use crate::range::Range;
+    use crate::workflow::{resolve_destination, Entry};
+
+    use super::*;
+
+    fn accepted_volume(destination: &Destination, entry: Entry<Range<u16>>, workflows: &HashMap<&str, Workflow>) -> u128 {
+        match destination {
+            Destination::Accepted => entry.permutation().unwrap(),
+            Destination::Rejected => 0,
+            Destination::Ref(name) => workflows[name.as_str()].create_branches(&entry)
+                .into_iter()
+                .map(|(entry, destination)| accepted_volume(destination, entry, workflows))
+                .sum(),
+        }
+    }
+
+    #[test]
+    fn test_optimize_example() {
+        let file = std::fs::read_to_string("example.txt").unwrap();
+        let (block, entries) = file.split_once("\n\n").unwrap();
+        let mut categories = Categories::default();
+        let workflows = parse_workflows(block, &mut categories);
+        let entries = parse_entries(entries, &mut categories);
+        let optimized = optimize(parse_workflows(block, &mut categories), &categories);
+
+        assert!(optimized.len() < workflows.len());
+        assert!(!optimized.contains_key("lnx"));
+        let start = Destination::Ref("in".to_string());
+        let full = Entry::filled(&categories, Range { start: 1, end: 4000 });
+        assert_eq!(accepted_volume(&start, full.clone(), &optimized), accepted_volume(&start, full, &workflows));
+        for entry in entries {
+            assert_eq!(resolve_destination(&entry, &start, &optimized), resolve_destination(&entry, &start, &workflows));
+        }
+    }
+
+    #[test]
+    fn test_remove_redundant_rules() {
+        let mut categories = Categories::default();
+        let workflows = parse_workflows("in{x>10:A,x>20:R,x<=10:R,A}\nlnx{m>1548:A,A}", &mut categories);
+        let optimized = optimize(workflows, &categories);
+
+        assert_eq!(print_workflows(&optimized, &categories), "in{x>10:A,R}");
+    }
+
+    #[test]
+    fn test_inline_single_use() {
+        let mut categories = Categories::default();
+        let workflows = parse_workflows("in{x>10:px,qqz}\npx{m<5:A,R}\nqqz{a>3:R,A}", &mut categories);
+        let optimized = optimize(workflows, &categories);
+
+        assert_eq!(print_workflows(&optimized, &categories), "in{x>10&&m<5:A,x>10:R,a>3:R,A}");
+    }
+
+    #[test]
+    fn test_keep_large_conditional_workflows() {
+        let mut categories = Categories::default();
+        let block = "in{x>10:px,A}\npx{m<5:A,a>3:R,s<2:A,R}";
+        let workflows = parse_workflows(block, &mut categories);
+        let optimized = optimize(workflows, &categories);
+
+        assert_eq!(print_workflows(&optimized, &categories), block);
+    }
+
+    #[test]
+    fn test_keep_shared_workflows() {
+        let mut categories = Categories::default();
+        let block = "in{x>10:px,m>5:px,qqz}\npx{m<5:A,R}\nqqz{a>3:R,A}";
+        let workflows = parse_workflows(block, &mut categories);
+        let optimized = optimize(workflows, &categories);
+
+        assert_eq!(print_workflows(&optimized, &categories), "in{x>10:px,m>5:px,a>3:R,A}\npx{m<5:A,R}");
+    }
+}
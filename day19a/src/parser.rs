@@ -3,68 +3,127 @@ use std::collections::HashMap;
 use nom::branch::alt;
 use nom::bytes::complete::{is_not, take_until, take_while};
 use nom::bytes::streaming::tag;
-use nom::character::{complete, streaming};
-use nom::character::complete::{char, one_of};
+use nom::character::complete;
+use nom::character::complete::{alpha1, char};
 use nom::error::{Error, ErrorKind, ParseError};
 use nom::IResult;
 use nom::multi::separated_list0;
-use nom::sequence::delimited;
+use nom::sequence::{delimited, separated_pair};
 
-use crate::types::{Category, Condition, Destination, Entry, Rule, Workflow};
+use crate::workflow::{Categories, Condition, Destination, Entry, Expression, Rule, Workflow};
 
-pub fn parse_workspace(input: &str) -> IResult<&str, Workflow> {
+pub fn parse_workflows<'a>(block: &'a str, categories: &mut Categories) -> HashMap<&'a str, Workflow> {
+    block.split("\n")
+        .map(|line| parse_workspace(line, categories).expect("Could not parse workspace").1)
+        .collect()
+}
+
+/// Parses all parts at once, so a category that only shows up in a later part is known to every entry.
+pub fn parse_entries(block: &str, categories: &mut Categories) -> Vec<Entry<u64>> {
+    let ratings: Vec<Vec<(&str, u64)>> = block.split("\n")
+        .filter(|line| !line.is_empty())
+        .map(|line| parse_ratings(line).expect("Could not parse entry").1)
+        .collect();
+    ratings.iter()
+        .flatten()
+        .for_each(|(name, _)| {
+            categories.intern(name);
+        });
+    ratings.iter()
+        .map(|ratings| create_entry(ratings, categories).expect("Could not find all categories"))
+        .collect()
+}
+
+pub fn parse_workspace<'a>(input: &'a str, categories: &mut Categories) -> IResult<&'a str, (&'a str, Workflow)> {
     let (input, name) = take_until("{")(input)?;
-    let (_, input) = delimited(char('{'), is_not("}"), char('}'))(input)?;
+    let (rest, input) = delimited(char('{'), is_not("}"), char('}'))(input)?;
     let (input, default) = split_last(",", input)?;
     let (_, default) = parse_destination(default)?;
-    let (_, rules) = separated_list0(char(','), parse_rule)(input)?;
+    let (_, rules) = separated_list0(char(','), |input| parse_rule(input, categories))(input)?;
 
-    Ok((name, Workflow {
+    Ok((rest, (name, Workflow {
         rules,
         default,
-    }))
+    })))
 }
 
 fn split_last<'a>(delimiter: &str, input: &'a str) -> IResult<&'a str, &'a str> {
     match input.rfind(delimiter) {
-        None => Ok((input, "")),
+        None => Ok(("", input)),
         Some(idx) => Ok((&input[..idx], &input[idx + delimiter.len()..])),
     }
 }
 
-fn parse_rule(input: &str) -> IResult<&str, Rule> {
-    let (input, category) = parse_category(input)?;
-    let (input, condition) = parse_condition(input)?;
-    let (input, value) = streaming::u64(input)?;
+fn parse_rule<'a>(input: &'a str, categories: &mut Categories) -> IResult<&'a str, Rule> {
+    let (input, expression) = parse_or(input, categories)?;
     let (input, _) = tag(":")(input)?;
     let (input, destination) = parse_destination(input)?;
 
     Ok((input, Rule {
-        category,
-        condition,
-        value,
+        expression,
         destination,
     }))
 }
 
-fn parse_category(input: &str) -> IResult<&str, Category> {
-    let (input, category) = one_of("xmas")(input)?;
-    match category {
-        'x' => Ok((input, Category::X)),
-        'm' => Ok((input, Category::M)),
-        'a' => Ok((input, Category::A)),
-        's' => Ok((input, Category::S)),
-        _ => Err(nom::Err::Error(Error::from_error_kind("", ErrorKind::OneOf)))
+/// `||` binds weakest, then `&&`, then `!`; parentheses group explicitly.
+fn parse_or<'a>(input: &'a str, categories: &mut Categories) -> IResult<&'a str, Expression> {
+    let (mut input, mut expression) = parse_and(input, categories)?;
+    while let Ok((rest, _)) = tag::<&str, &str, Error<&str>>("||")(input) {
+        let (rest, right) = parse_and(rest, categories)?;
+        expression = Expression::Or(Box::new(expression), Box::new(right));
+        input = rest;
+    }
+    Ok((input, expression))
+}
+
+fn parse_and<'a>(input: &'a str, categories: &mut Categories) -> IResult<&'a str, Expression> {
+    let (mut input, mut expression) = parse_unary(input, categories)?;
+    while let Ok((rest, _)) = tag::<&str, &str, Error<&str>>("&&")(input) {
+        let (rest, right) = parse_unary(rest, categories)?;
+        expression = Expression::And(Box::new(expression), Box::new(right));
+        input = rest;
     }
+    Ok((input, expression))
 }
 
-fn parse_condition(input: &str) -> IResult<&str, Condition> {
-    let (input, condition) = one_of("<>")(input)?;
-    match condition {
-        '>' => Ok((input, Condition::Larger)),
-        '<' => Ok((input, Condition::Lower)),
-        _ => Err(nom::Err::Error(Error::from_error_kind("", ErrorKind::OneOf)))
+fn parse_unary<'a>(input: &'a str, categories: &mut Categories) -> IResult<&'a str, Expression> {
+    if let Some(rest) = input.strip_prefix('!') {
+        let (rest, expression) = parse_unary(rest, categories)?;
+        return Ok((rest, Expression::Not(Box::new(expression))));
+    }
+    if let Some(rest) = input.strip_prefix('(') {
+        let (rest, expression) = parse_or(rest, categories)?;
+        let (rest, _) = char(')')(rest)?;
+        return Ok((rest, expression));
     }
+    let (input, category) = alpha1(input)?;
+    let (input, (condition, value)) = parse_condition(input)?;
+    Ok((input, Expression::compare(categories.intern(category), condition, value)))
+}
+
+fn parse_condition(input: &str) -> IResult<&str, (Condition, u64)> {
+    alt((parse_membership, parse_comparison))(input)
+}
+
+fn parse_membership(input: &str) -> IResult<&str, (Condition, u64)> {
+    let (input, _) = tag(" in ")(input)?;
+    let (input, (start, end)) = separated_pair(complete::u64, tag(".."), complete::u64)(input)?;
+    Ok((input, (Condition::In(end), start)))
+}
+
+fn parse_comparison(input: &str) -> IResult<&str, (Condition, u64)> {
+    let (input, operator) = alt((tag("<="), tag(">="), tag("=="), tag("!="), tag("<"), tag(">")))(input)?;
+    let (input, value) = complete::u64(input)?;
+    let condition = match operator {
+        ">" => Condition::Larger,
+        "<" => Condition::Lower,
+        ">=" => Condition::LargerOrEqual,
+        "<=" => Condition::LowerOrEqual,
+        "==" => Condition::Equal,
+        "!=" => Condition::NotEqual,
+        _ => return Err(nom::Err::Error(Error::from_error_kind(input, ErrorKind::Tag))),
+    };
+    Ok((input, (condition, value)))
 }
 
 fn parse_destination(input: &str) -> IResult<&str, Destination> {
@@ -76,81 +135,168 @@ fn parse_destination(input: &str) -> IResult<&str, Destination> {
     }
 }
 
-pub fn parse_entry(input: &str) -> IResult<&str, Entry> {
-    let (_, input) = delimited(char('{'), is_not("}"), char('}'))(input)?;
-    let (_, input) = separated_list0(char(','), parse_rating)(input)?;
-    let entries: HashMap<Category, u64> = HashMap::from_iter(input);
-    let entry = Entry {
-        x: *entries.get(&Category::X).expect("Could not find X"),
-        m: *entries.get(&Category::M).expect("Could not find M"),
-        a: *entries.get(&Category::A).expect("Could not find A"),
-        s: *entries.get(&Category::S).expect("Could not find S"),
-    };
-    Ok(("", entry))
+pub fn parse_entry<'a>(input: &'a str, categories: &mut Categories) -> IResult<&'a str, Entry<u64>> {
+    let (rest, ratings) = parse_ratings(input)?;
+    ratings.iter().for_each(|(name, _)| {
+        categories.intern(name);
+    });
+    match create_entry(&ratings, categories) {
+        Some(entry) => Ok((rest, entry)),
+        None => Err(nom::Err::Failure(Error::from_error_kind(input, ErrorKind::Verify))),
+    }
+}
+
+fn parse_ratings(input: &str) -> IResult<&str, Vec<(&str, u64)>> {
+    let (rest, input) = delimited(char('{'), is_not("}"), char('}'))(input)?;
+    let (_, ratings) = separated_list0(char(','), parse_rating)(input)?;
+    Ok((rest, ratings))
 }
 
-fn parse_rating(input: &str) -> IResult<&str, (Category, u64)> {
-    let (input, category) = parse_category(input)?;
+fn parse_rating(input: &str) -> IResult<&str, (&str, u64)> {
+    let (input, category) = alpha1(input)?;
     let (input, _) = tag("=")(input)?;
     let (input, value) = complete::u64(input)?;
     Ok((input, (category, value)))
 }
 
+/// Orders the ratings by category id, `None` if a known category has no rating.
+fn create_entry(ratings: &[(&str, u64)], categories: &Categories) -> Option<Entry<u64>> {
+    let ratings: HashMap<&str, u64> = ratings.iter().copied().collect();
+    categories.iter()
+        .map(|category| ratings.get(categories.name(category)).copied())
+        .collect::<Option<Vec<u64>>>()
+        .map(Entry::new)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::types::Entry;
+    use crate::workflow::Category;
 
     use super::*;
 
     #[test]
     fn test_rating() {
         let input = "{x=2205,m=86,a=2846,s=1277}";
-        assert_eq!(parse_entry(input), Ok(("", Entry {
-            x: 2205,
-            m: 86,
-            a: 2846,
-            s: 1277,
-        })))
+        let mut categories = Categories::default();
+
+        assert_eq!(parse_entry(input, &mut categories), Ok(("", Entry::new(vec![2205, 86, 2846, 1277]))));
+        assert_eq!(categories.get("a"), Some(Category(2)));
+    }
+
+    #[test]
+    fn test_rating_missing_category() {
+        let mut categories = Categories::default();
+        categories.intern("z");
+
+        assert!(parse_entry("{x=1,m=2}", &mut categories).is_err());
+    }
+
+    #[test]
+    fn test_entries_discover_categories() {
+        let mut categories = Categories::default();
+        let entries = parse_entries("{x=1,m=2}\n{m=3,x=4}", &mut categories);
+
+        assert_eq!(entries, vec![Entry::new(vec![1, 2]), Entry::new(vec![4, 3])]);
     }
 
     #[test]
     fn test_workspace() {
         let input = "gv{a>1626:A,x<2292:ex,a<1391:R,R}";
+        let mut categories = Categories::default();
 
-        assert_eq!(parse_workspace(input), Ok(("", ("gv", Workflow {
+        assert_eq!(parse_workspace(input, &mut categories), Ok(("", ("gv", Workflow {
             rules: vec![
                 Rule {
-                    category: Category::A,
-                    condition: Condition::Larger,
-                    value: 1626,
+                    expression: Expression::compare(Category(0), Condition::Larger, 1626),
                     destination: Destination::Accepted,
                 },
                 Rule {
-                    category: Category::X,
-                    condition: Condition::Lower,
-                    value: 2292,
+                    expression: Expression::compare(Category(1), Condition::Lower, 2292),
                     destination: Destination::Ref("ex".to_string()),
                 },
                 Rule {
-                    category: Category::A,
-                    condition: Condition::Lower,
-                    value: 1391,
+                    expression: Expression::compare(Category(0), Condition::Lower, 1391),
                     destination: Destination::Rejected,
                 },
             ],
             default: Destination::Rejected,
         }))));
+        assert_eq!(categories.name(Category(1)), "x");
     }
 
     #[test]
     fn test_rule() {
         let input = "a>1626:A";
+        let mut categories = Categories::default();
+
+        assert_eq!(parse_rule(input, &mut categories), Ok(("", Rule {
+            expression: Expression::compare(Category(0), Condition::Larger, 1626),
+            destination: Destination::Accepted,
+        })));
+    }
+
+    #[test]
+    fn test_rule_operators() {
+        let cases = vec![
+            ("a<5:A", Condition::Lower, 5),
+            ("a>5:A", Condition::Larger, 5),
+            ("a<=5:A", Condition::LowerOrEqual, 5),
+            ("a>=5:A", Condition::LargerOrEqual, 5),
+            ("a==5:A", Condition::Equal, 5),
+            ("a!=5:A", Condition::NotEqual, 5),
+            ("a in 10..200:A", Condition::In(200), 10),
+        ];
+
+        for (input, condition, value) in cases {
+            let mut categories = Categories::default();
+            assert_eq!(parse_rule(input, &mut categories), Ok(("", Rule {
+                expression: Expression::compare(Category(0), condition, value),
+                destination: Destination::Accepted,
+            })), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_rule_compound() {
+        let input = "x>10&&m<200||!(a==1||s!=2):A";
+        let mut categories = Categories::default();
+        let compare = |category, condition, value| Box::new(Expression::compare(Category(category), condition, value));
 
-        assert_eq!(parse_rule(input), Ok(("", Rule {
-            category: Category::A,
-            condition: Condition::Larger,
-            value: 1626,
+        assert_eq!(parse_rule(input, &mut categories), Ok(("", Rule {
+            expression: Expression::Or(
+                Box::new(Expression::And(compare(0, Condition::Larger, 10), compare(1, Condition::Lower, 200))),
+                Box::new(Expression::Not(Box::new(Expression::Or(compare(2, Condition::Equal, 1), compare(3, Condition::NotEqual, 2))))),
+            ),
             destination: Destination::Accepted,
         })));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_rule_unclosed_group() {
+        let mut categories = Categories::default();
+
+        assert!(parse_rule("(x>10:A", &mut categories).is_err());
+    }
+
+    #[test]
+    fn test_workspace_with_range() {
+        let mut categories = Categories::default();
+        let (_, (_, workflow)) = parse_workspace("px{a in 1..10:qkq,m!=4:A,rfg}", &mut categories).unwrap();
+
+        assert_eq!(workflow.rules[0].expression, Expression::compare(Category(0), Condition::In(10), 1));
+        assert_eq!(workflow.rules[1].expression, Expression::compare(Category(1), Condition::NotEqual, 4));
+        assert_eq!(workflow.default, Destination::Ref("rfg".to_string()));
+    }
+
+    #[test]
+    fn test_rule_long_category() {
+        let input = "weight<20:heavy";
+        let mut categories = Categories::default();
+        categories.intern("x");
+
+        assert_eq!(parse_rule(input, &mut categories), Ok(("", Rule {
+            expression: Expression::compare(Category(1), Condition::Lower, 20),
+            destination: Destination::Ref("heavy".to_string()),
+        })));
+    }
+}
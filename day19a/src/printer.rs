@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use crate::workflow::{Categories, Condition, Destination, Entry, Expression, Rule, Workflow};
+
+/// Formats a value in puzzle syntax, looking up the category names it refers to.
+pub struct Printer<'a, T> {
+    value: &'a T,
+    categories: &'a Categories,
+}
+
+pub struct WorkflowPrinter<'a> {
+    name: &'a str,
+    workflow: &'a Workflow,
+    categories: &'a Categories,
+}
+
+/// Prints workflows in puzzle syntax, `in` first and the rest sorted by name.
+pub fn print_workflows(workflows: &HashMap<&str, Workflow>, categories: &Categories) -> String {
+    let mut names: Vec<&str> = workflows.keys().copied().collect();
+    names.sort_by_key(|name| (*name != "in", *name));
+    names.iter()
+        .map(|name| workflows[name].display(name, categories).to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl Workflow {
+    pub fn display<'a>(&'a self, name: &'a str, categories: &'a Categories) -> WorkflowPrinter<'a> {
+        WorkflowPrinter {
+            name,
+            workflow: self,
+            categories,
+        }
+    }
+}
+
+impl Rule {
+    pub fn display<'a>(&'a self, categories: &'a Categories) -> Printer<'a, Rule> {
+        Printer { value: self, categories }
+    }
+}
+
+impl Expression {
+    pub fn display<'a>(&'a self, categories: &'a Categories) -> Printer<'a, Expression> {
+        Printer { value: self, categories }
+    }
+}
+
+impl Entry<u64> {
+    pub fn display<'a>(&'a self, categories: &'a Categories) -> Printer<'a, Entry<u64>> {
+        Printer { value: self, categories }
+    }
+}
+
+impl Display for WorkflowPrinter<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{{", self.name)?;
+        for rule in &self.workflow.rules {
+            write!(f, "{},", rule.display(self.categories))?;
+        }
+        write!(f, "{}}}", self.workflow.default)
+    }
+}
+
+impl Display for Printer<'_, Rule> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.value.expression.display(self.categories), self.value.destination)
+    }
+}
+
+/// Adds parentheses wherever the parser's precedence and left associativity would otherwise regroup the expression.
+impl Display for Printer<'_, Expression> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let grouped = |f: &mut Formatter<'_>, expression: &Expression, group: bool| if group {
+            write!(f, "({})", expression.display(self.categories))
+        } else {
+            write!(f, "{}", expression.display(self.categories))
+        };
+        match self.value {
+            Expression::Compare(comparison) => {
+                let name = self.categories.name(comparison.category);
+                match comparison.condition {
+                    Condition::In(end) => write!(f, "{} in {}..{}", name, comparison.value, end),
+                    condition => write!(f, "{}{}{}", name, operator(condition), comparison.value),
+                }
+            }
+            Expression::And(left, right) => {
+                grouped(f, left, matches!(**left, Expression::Or(..)))?;
+                write!(f, "&&")?;
+                grouped(f, right, matches!(**right, Expression::Or(..) | Expression::And(..)))
+            }
+            Expression::Or(left, right) => {
+                grouped(f, left, false)?;
+                write!(f, "||")?;
+                grouped(f, right, matches!(**right, Expression::Or(..)))
+            }
+            Expression::Not(expression) => {
+                write!(f, "!")?;
+                grouped(f, expression, matches!(**expression, Expression::Or(..) | Expression::And(..)))
+            }
+        }
+    }
+}
+
+impl Display for Printer<'_, Entry<u64>> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let ratings: Vec<String> = self.categories.iter()
+            .map(|category| format!("{}={}", self.categories.name(category), self.value[category]))
+            .collect();
+        write!(f, "{{{}}}", ratings.join(","))
+    }
+}
+
+impl Display for Destination {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Destination::Accepted => write!(f, "A"),
+            Destination::Rejected => write!(f, "R"),
+            Destination::Ref(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+fn operator(condition: Condition) -> &'static str {
+    match condition {
+        Condition::Larger => ">",
+        Condition::Lower => "<",
+        Condition::LargerOrEqual => ">=",
+        Condition::LowerOrEqual => "<=",
+        Condition::Equal => "==",
+        Condition::NotEqual => "!=",
+        Condition::In(_) => unreachable!("membership is printed as a range"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::parser::{parse_entry, parse_workflows, parse_workspace};
+    use crate::workflow::Category;
+
+    use super::*;
+
+    const NAMES: [&str; 4] = ["x", "m", "a", "s"];
+
+    fn categories() -> Categories {
+        let mut categories = Categories::default();
+        for name in NAMES {
+            categories.intern(name);
+        }
+        categories
+    }
+
+    fn condition() -> impl Strategy<Value=Condition> {
+        prop_oneof![
+            Just(Condition::Larger),
+            Just(Condition::Lower),
+            Just(Condition::LargerOrEqual),
+            Just(Condition::LowerOrEqual),
+            Just(Condition::Equal),
+            Just(Condition::NotEqual),
+            any::<u64>().prop_map(Condition::In),
+        ]
+    }
+
+    fn expression() -> impl Strategy<Value=Expression> {
+        let leaf = (0..NAMES.len(), condition(), any::<u64>())
+            .prop_map(|(category, condition, value)| Expression::compare(Category(category), condition, value));
+        leaf.prop_recursive(4, 16, 2, |inner| prop_oneof![
+            (inner.clone(), inner.clone()).prop_map(|(left, right)| Expression::And(Box::new(left), Box::new(right))),
+            (inner.clone(), inner.clone()).prop_map(|(left, right)| Expression::Or(Box::new(left), Box::new(right))),
+            inner.prop_map(|expression| Expression::Not(Box::new(expression))),
+        ])
+    }
+
+    fn destination() -> impl Strategy<Value=Destination> {
+        prop_oneof![
+            Just(Destination::Accepted),
+            Just(Destination::Rejected),
+            "[a-z]{1,3}".prop_map(Destination::Ref),
+        ]
+    }
+
+    fn workflow() -> impl Strategy<Value=Workflow> {
+        let rule = (expression(), destination()).prop_map(|(expression, destination)| Rule { expression, destination });
+        (prop::collection::vec(rule, 0..4), destination()).prop_map(|(rules, default)| Workflow { rules, default })
+    }
+
+    proptest! {
+        #[test]
+        fn test_workflow_round_trip(name in "[a-z]{1,3}", workflow in workflow()) {
+            let mut categories = categories();
+            let printed = workflow.display(&name, &categories).to_string();
+
+            prop_assert_eq!(parse_workspace(&printed, &mut categories), Ok(("", (name.as_str(), workflow))));
+        }
+
+        #[test]
+        fn test_entry_round_trip(values in prop::collection::vec(any::<u64>(), NAMES.len())) {
+            let mut categories = categories();
+            let entry = Entry::new(values);
+            let printed = entry.display(&categories).to_string();
+
+            prop_assert_eq!(parse_entry(&printed, &mut categories), Ok(("", entry)));
+        }
+    }
+
+    #[test]
+    fn test_print_example() {
+        let block = "in{s<1351:px,qqz}\nlnx{m>1548:A,A}\npx{a<2006:qkq,m>2090:A,rfg}";
+        let mut categories = Categories::default();
+        let workflows = parse_workflows(block, &mut categories);
+
+        assert_eq!(print_workflows(&workflows, &categories), block);
+    }
+
+    #[test]
+    fn test_print_expression_groups() {
+        let input = "in{x>10&&(m<200||a==1):A,!(s!=2&&x in 1..5)||!m<=3:R,x>1||m>1||a>=2:A,R}";
+        let mut categories = Categories::default();
+        let workflows = parse_workflows(input, &mut categories);
+
+        assert_eq!(print_workflows(&workflows, &categories), input);
+    }
+
+    #[test]
+    fn test_print_entry() {
+        let categories = categories();
+        let entry = Entry::new(vec![787, 2655, 1222, 2876]);
+
+        assert_eq!(entry.display(&categories).to_string(), "{x=787,m=2655,a=1222,s=2876}");
+    }
+}
@@ -0,0 +1,273 @@
+use std::fmt::Debug;
+
+use crate::workflow::{Category, Condition, Domain, Entry};
+
+/// Inclusive range of ratings, stored in whichever integer type is wide enough for the bounds.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Range<T> {
+    pub start: T,
+    pub end: T,
+}
+
+/// Integer types a range can be stored in.
+pub trait Rating: Copy + Ord + Debug + Into<u64> + TryFrom<u64> {}
+
+impl<T: Copy + Ord + Debug + Into<u64> + TryFrom<u64>> Rating for T {}
+
+impl<T: Rating> Entry<Range<T>> {
+    /// Number of parts in the box, `None` if it does not fit into a `u128`.
+    pub fn permutation(&self) -> Option<u128> {
+        self.values()
+            .iter()
+            .try_fold(1u128, |product, range| product.checked_mul(range.permutation()))
+    }
+
+    /// The box of parts inside both boxes, `None` if they do not overlap.
+    pub fn intersect(&self, other: &Self) -> Option<Self> {
+        let values = self.values().iter()
+            .zip(other.values())
+            .map(|(range, other)| range.intersect(other))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Entry::new(values))
+    }
+
+    pub fn contains(&self, entry: &Entry<u64>) -> bool {
+        self.values().iter()
+            .zip(entry.values())
+            .all(|(range, rating)| range.contains(*rating))
+    }
+}
+
+impl<T: Rating> Domain for Entry<Range<T>> {
+    fn split(&self, category: Category, condition: Condition, value: u64) -> (Vec<Self>, Vec<Self>) {
+        let with_range = |range: Range<T>| {
+            let mut clone = self.clone();
+            clone[category] = range;
+            clone
+        };
+        let (inside, outside) = self[category].split(value, condition);
+        (inside.into_iter().map(with_range).collect(), outside.into_iter().map(with_range).collect())
+    }
+}
+
+impl<T: Rating> Range<T> {
+    /// Fails if either end does not fit into `T`.
+    pub fn new(start: u64, end: u64) -> Option<Self> {
+        Some(Range {
+            start: T::try_from(start).ok()?,
+            end: T::try_from(end).ok()?,
+        })
+    }
+
+    /// Returns the disjoint ranges inside (left) and outside (right) of the condition
+    fn split(&self, value: u64, condition: Condition) -> (Vec<Self>, Vec<Self>) {
+        let (start, end) = (self.start.into(), self.end.into());
+        let mut inside = Vec::new();
+        let mut outside = Vec::new();
+        let mut next = Some(start);
+        for (lower, upper) in condition.intervals(value) {
+            let (lower, upper) = (lower.max(start), upper.min(end));
+            if lower > upper {
+                continue;
+            }
+            if let Some(next) = next.filter(|next| *next < lower) {
+                outside.push(Range::from(next, lower - 1));
+            }
+            inside.push(Range::from(lower, upper));
+            next = upper.checked_add(1);
+        }
+        if let Some(next) = next.filter(|next| *next <= end) {
+            outside.push(Range::from(next, end));
+        }
+        (inside, outside)
+    }
+
+    pub fn contains(&self, rating: u64) -> bool {
+        (self.start.into()..=self.end.into()).contains(&rating)
+    }
+
+    pub fn intersect(&self, other: &Self) -> Option<Self> {
+        let (start, end) = (self.start.max(other.start), self.end.min(other.end));
+        (start <= end).then_some(Range { start, end })
+    }
+
+    fn permutation(&self) -> u128 {
+        (self.end.into() - self.start.into()) as u128 + 1
+    }
+
+    /// Only called with values between `start` and `end`, which always fit into `T`.
+    fn from(start: u64, end: u64) -> Self {
+        Self::new(start, end).expect("Could not narrow range")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::workflow::Expression;
+
+    use super::*;
+
+    #[test]
+    fn test_split1() {
+        let range: Range<u16> = Range { start: 1, end: 10 };
+        let condition = Condition::Lower;
+        let value = 5;
+
+        assert_eq!(range.split(value, condition), (vec![Range::from(1, 4)], vec![Range::from(5, 10)]));
+    }
+
+    #[test]
+    fn test_split2() {
+        let range: Range<u16> = Range { start: 1, end: 10 };
+        let condition = Condition::Larger;
+        let value = 5;
+
+        assert_eq!(range.split(value, condition), (vec![Range::from(6, 10)], vec![Range::from(1, 5)]));
+    }
+
+    #[test]
+    fn test_split3() {
+        let range: Range<u16> = Range { start: 1, end: 10 };
+        let condition = Condition::Larger;
+        let value = 11;
+
+        assert_eq!(range.split(value, condition), (vec![], vec![Range::from(1, 10)]));
+    }
+
+    #[test]
+    fn test_split4() {
+        let range: Range<u16> = Range { start: 1, end: 10 };
+        let condition = Condition::Lower;
+        let value = 11;
+
+        assert_eq!(range.split(value, condition), (vec![Range::from(1, 10)], vec![]));
+    }
+
+    #[test]
+    fn test_split5() {
+        let range: Range<u16> = Range { start: 11, end: 20 };
+        let condition = Condition::Larger;
+        let value = 8;
+
+        assert_eq!(range.split(value, condition), (vec![Range::from(11, 20)], vec![]));
+    }
+
+    #[test]
+    fn test_split6() {
+        let range: Range<u16> = Range { start: 11, end: 20 };
+        let condition = Condition::Lower;
+        let value = 8;
+
+        assert_eq!(range.split(value, condition), (vec![], vec![Range::from(11, 20)]));
+    }
+
+    #[test]
+    fn test_split_boundaries() {
+        let range: Range<u16> = Range { start: 1, end: 10 };
+        let cases = vec![
+            (Condition::Larger, 1, vec![Range::from(2, 10)]),
+            (Condition::Larger, 10, vec![]),
+            (Condition::Lower, 1, vec![]),
+            (Condition::Lower, 10, vec![Range::from(1, 9)]),
+            (Condition::LargerOrEqual, 1, vec![Range::from(1, 10)]),
+            (Condition::LargerOrEqual, 10, vec![Range::from(10, 10)]),
+            (Condition::LargerOrEqual, 11, vec![]),
+            (Condition::LowerOrEqual, 0, vec![]),
+            (Condition::LowerOrEqual, 1, vec![Range::from(1, 1)]),
+            (Condition::LowerOrEqual, 10, vec![Range::from(1, 10)]),
+            (Condition::Equal, 1, vec![Range::from(1, 1)]),
+            (Condition::Equal, 10, vec![Range::from(10, 10)]),
+            (Condition::Equal, 11, vec![]),
+            (Condition::NotEqual, 1, vec![Range::from(2, 10)]),
+            (Condition::NotEqual, 5, vec![Range::from(1, 4), Range::from(6, 10)]),
+            (Condition::NotEqual, 10, vec![Range::from(1, 9)]),
+            (Condition::In(10), 1, vec![Range::from(1, 9)]),
+            (Condition::In(11), 10, vec![Range::from(10, 10)]),
+            (Condition::In(5), 5, vec![]),
+            (Condition::In(20), 0, vec![Range::from(1, 10)]),
+        ];
+
+        for (condition, value, inside) in cases {
+            let (found_inside, outside) = range.split(value, condition);
+            assert_eq!(found_inside, inside, "{:?} {}", condition, value);
+            let covered: u128 = found_inside.iter().chain(outside.iter()).map(Range::permutation).sum();
+            assert_eq!(covered, 10, "{:?} {}", condition, value);
+        }
+    }
+
+    #[test]
+    fn test_split_not_equal_outside() {
+        let range: Range<u16> = Range { start: 1, end: 10 };
+
+        assert_eq!(range.split(5, Condition::NotEqual), (vec![Range::from(1, 4), Range::from(6, 10)], vec![Range::from(5, 5)]));
+        assert_eq!(range.split(3, Condition::In(6)), (vec![Range::from(3, 5)], vec![Range::from(1, 2), Range::from(6, 10)]));
+    }
+
+    #[test]
+    fn test_split_compound_matches_brute_force() {
+        let compare = |category, condition, value| Box::new(Expression::compare(Category(category), condition, value));
+        let expression = Expression::Or(
+            Box::new(Expression::And(compare(0, Condition::Larger, 3), compare(1, Condition::Lower, 7))),
+            Box::new(Expression::Not(Box::new(Expression::Or(compare(0, Condition::NotEqual, 2), compare(1, Condition::Equal, 9))))),
+        );
+        let entry = Entry::new(vec![Range::<u16>::from(1, 10), Range::from(1, 10)]);
+
+        let (inside, outside) = expression.split(&entry);
+        let expected = (1..=10)
+            .flat_map(|x| (1..=10).map(move |m| Entry::new(vec![x, m])))
+            .filter(|part| expression.holds(part))
+            .count() as u128;
+        assert_eq!(inside.iter().map(|part| part.permutation().unwrap()).sum::<u128>(), expected);
+        assert_eq!(outside.iter().map(|part| part.permutation().unwrap()).sum::<u128>(), 100 - expected);
+    }
+
+    #[test]
+    fn test_entry_permutation() {
+        let entry: Entry<Range<u16>> = Entry::new(vec![
+            Range { start: 1, end: 1 },
+            Range { start: 1, end: 5 },
+            Range { start: 1, end: 1 },
+            Range { start: 1, end: 10 },
+        ]);
+
+        assert_eq!(entry.permutation(), Some(50));
+    }
+
+    #[test]
+    fn test_permutation_overflow() {
+        let full = Range { start: 0, end: u64::MAX };
+
+        assert_eq!(Entry::new(vec![full]).permutation(), Some(1 << 64));
+        assert_eq!(Entry::new(vec![full, full]).permutation(), None);
+    }
+
+    #[test]
+    fn test_split_wide_range() {
+        let range: Range<u32> = Range { start: 1, end: 100_000 };
+
+        assert_eq!(range.split(70_000, Condition::Larger), (vec![Range::from(70_001, 100_000)], vec![Range::from(1, 70_000)]));
+        let full = Range { start: 0, end: u64::MAX };
+        assert_eq!(full.split(u64::MAX, Condition::Lower), (vec![Range::from(0, u64::MAX - 1)], vec![Range::from(u64::MAX, u64::MAX)]));
+    }
+
+    #[test]
+    fn test_range_new() {
+        assert_eq!(Range::<u16>::new(1, 65_535), Some(Range { start: 1, end: 65_535 }));
+        assert_eq!(Range::<u16>::new(1, 65_536), None);
+    }
+
+    #[test]
+    fn test_range_permutation() {
+        let range: Range<u16> = Range { start: 1, end: 4 };
+
+        assert_eq!(range.permutation(), 4);
+    }
+
+    #[test]
+    fn test_range_permutation2() {
+        let range: Range<u16> = Range { start: 3990, end: 4000 };
+
+        assert_eq!(range.permutation(), 11);
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use crate::range::{Range, Rating};
+use crate::workflow::{Destination, Entry, Workflow};
+
+/// A box of parts that is decided as a whole, together with the workflows it passed through.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Branch<'a, T> {
+    pub part: Entry<Range<T>>,
+    /// Names of the visited workflows, starting with the entry point.
+    pub path: Vec<&'a str>,
+    pub destination: &'a Destination,
+}
+
+/// All accepted parts as disjoint boxes.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Region<'a, T> {
+    branches: Vec<Branch<'a, T>>,
+}
+
+/// Splits the box into disjoint branches, each ending in `Accepted` or `Rejected`.
+pub fn resolve<'a, T: Rating>(workflows: &'a HashMap<&str, Workflow>, start: &'a str, part: Entry<Range<T>>) -> Vec<Branch<'a, T>> {
+    let mut pending = vec![(part, vec![start])];
+    let mut branches = Vec::new();
+    while let Some((part, path)) = pending.pop() {
+        let name = path.last().expect("Could not find current workflow");
+        let workflow = workflows.get(name).expect("Could not resolve name");
+        for (part, destination) in workflow.create_branches(&part) {
+            match destination {
+                Destination::Ref(name) => {
+                    let mut path = path.clone();
+                    path.push(name);
+                    pending.push((part, path));
+                }
+                _ => branches.push(Branch {
+                    part,
+                    path: path.clone(),
+                    destination,
+                }),
+            }
+        }
+    }
+    branches
+}
+
+impl<'a, T: Rating> Region<'a, T> {
+    pub fn accepted(workflows: &'a HashMap<&str, Workflow>, start: &'a str, part: Entry<Range<T>>) -> Self {
+        let branches = resolve(workflows, start, part).into_iter()
+            .filter(|branch| *branch.destination == Destination::Accepted)
+            .collect();
+        Self { branches }
+    }
+
+    pub fn branches(&self) -> &[Branch<'a, T>] {
+        &self.branches
+    }
+
+    /// Number of accepted parts, `None` if it does not fit into a `u128`.
+    pub fn volume(&self) -> Option<u128> {
+        self.branches.iter()
+            .try_fold(0u128, |volume, branch| volume.checked_add(branch.part.permutation()?))
+    }
+
+    /// Number of accepted parts inside the query box, e.g. all parts with `x` in `1..=100`.
+    pub fn volume_within(&self, query: &Entry<Range<T>>) -> Option<u128> {
+        self.branches.iter()
+            .filter_map(|branch| branch.part.intersect(query))
+            .try_fold(0u128, |volume, part| volume.checked_add(part.permutation()?))
+    }
+
+    /// Accepted parts inside the query box, split into sub-boxes with the workflows that accepted each of them.
+    pub fn branches_within(&self, query: &Entry<Range<T>>) -> Vec<Branch<'a, T>> {
+        self.branches.iter()
+            .filter_map(|branch| Some(Branch {
+                part: branch.part.intersect(query)?,
+                path: branch.path.clone(),
+                destination: branch.destination,
+            }))
+            .collect()
+    }
+
+    pub fn contains(&self, entry: &Entry<u64>) -> bool {
+        self.path(entry).is_some()
+    }
+
+    /// Workflows that accepted the part, `None` if it is rejected.
+    pub fn path(&self, entry: &Entry<u64>) -> Option<&[&'a str]> {
+        self.branches.iter()
+            .find(|branch| branch.part.contains(entry))
+            .map(|branch| branch.path.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{parse_entries, parse_workflows};
+    use crate::workflow::{resolve_destination, Categories};
+
+    use super::*;
+
+    #[test]
+    fn test_region_example() {
+        let file = std::fs::read_to_string("example.txt").unwrap();
+        let (block, entries) = file.split_once("\n\n").unwrap();
+        let mut categories = Categories::default();
+        let workflows = parse_workflows(block, &mut categories);
+        let entries = parse_entries(entries, &mut categories);
+        let region = Region::accepted(&workflows, "in", Entry::filled(&categories, Range { start: 1u16, end: 4000 }));
+
+        assert_eq!(region.volume(), Some(167409079868000));
+        let start = Destination::Ref("in".to_string());
+        for entry in &entries {
+            assert_eq!(region.contains(entry), resolve_destination(entry, &start, &workflows));
+        }
+        assert_eq!(region.path(&entries[0]), Some(["in", "qqz", "qs", "lnx"].as_slice()));
+        assert_eq!(region.path(&entries[1]), None);
+    }
+
+    #[test]
+    fn test_volume_within() {
+        let mut categories = Categories::default();
+        let workflows = parse_workflows("in{x>5:px,m<3:A,R}\npx{x<9&&m>1:A,R}", &mut categories);
+        let full = Entry::filled(&categories, Range { start: 1u16, end: 10 });
+        let region = Region::accepted(&workflows, "in", full.clone());
+        let mut query = full;
+        query[categories.get("x").unwrap()] = Range { start: 4, end: 7 };
+
+        let start = Destination::Ref("in".to_string());
+        let expected = (4..=7)
+            .flat_map(|x| (1..=10).map(move |m| Entry::new(vec![x, m])))
+            .filter(|entry| resolve_destination(entry, &start, &workflows))
+            .count() as u128;
+        assert_eq!(region.volume_within(&query), Some(expected));
+    }
+
+    #[test]
+    fn test_branches_within() {
+        let mut categories = Categories::default();
+        let workflows = parse_workflows("in{x>5:px,m<3:A,R}\npx{x<9&&m>1:A,R}", &mut categories);
+        let full = Entry::filled(&categories, Range { start: 1u16, end: 10 });
+        let region = Region::accepted(&workflows, "in", full.clone());
+        let mut query = full;
+        query[categories.get("x").unwrap()] = Range { start: 4, end: 7 };
+        let branches = region.branches_within(&query);
+
+        let start = Destination::Ref("in".to_string());
+        for x in 1..=10 {
+            for m in 1..=10 {
+                let entry = Entry::new(vec![x, m]);
+                let matching: Vec<_> = branches.iter().filter(|branch| branch.part.contains(&entry)).collect();
+                let expected = (4..=7).contains(&x) && resolve_destination(&entry, &start, &workflows);
+                assert_eq!(matching.len(), usize::from(expected));
+                if let Some(branch) = matching.first() {
+                    assert_eq!(Some(branch.path.as_slice()), region.path(&entry));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_resolve_paths() {
+        let mut categories = Categories::default();
+        let workflows = parse_workflows("in{x>5:px,R}\npx{m<3:A,R}", &mut categories);
+        let part = Entry::new(vec![Range { start: 6u16, end: 10 }, Range { start: 1, end: 2 }]);
+
+        assert_eq!(resolve(&workflows, "in", part.clone()), vec![Branch {
+            part,
+            path: vec!["in", "px"],
+            destination: &Destination::Accepted,
+        }]);
+    }
+}
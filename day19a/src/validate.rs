@@ -0,0 +1,204 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use itertools::Itertools;
+
+use crate::range::Range;
+use crate::workflow::{Categories, Entry, Workflow};
+
+#[derive(Debug, PartialEq)]
+pub enum Finding {
+    MissingEntryPoint,
+    UndefinedReference { workflow: String, reference: String },
+    Cycle { workflows: Vec<String> },
+    UnreachableWorkflow { workflow: String },
+    /// A rule whose condition is already covered by the rules before it.
+    UnreachableRule { workflow: String, rule: usize },
+    UnreachableDefault { workflow: String },
+}
+
+/// Checks the whole workflow graph and reports every finding instead of stopping at the first one.
+pub fn validate(workflows: &HashMap<&str, Workflow>, categories: &Categories) -> Vec<Finding> {
+    let mut names: Vec<&str> = workflows.keys().copied().collect();
+    names.sort();
+
+    let mut findings = Vec::new();
+    if !workflows.contains_key("in") {
+        findings.push(Finding::MissingEntryPoint);
+    }
+    for name in &names {
+        for reference in workflows[name].references() {
+            if !workflows.contains_key(reference) {
+                findings.push(Finding::UndefinedReference {
+                    workflow: name.to_string(),
+                    reference: reference.to_string(),
+                });
+            }
+        }
+    }
+    findings.extend(find_cycles(&names, workflows));
+    if workflows.contains_key("in") {
+        let reachable = find_reachable("in", workflows);
+        findings.extend(names.iter()
+            .filter(|name| !reachable.contains(*name))
+            .map(|name| Finding::UnreachableWorkflow { workflow: name.to_string() }));
+    }
+    for name in &names {
+        findings.extend(find_unreachable_rules(name, &workflows[name], categories));
+    }
+    findings
+}
+
+/// Fails with every finding at once if any of them makes the workflows unsolvable.
+pub fn check(workflows: &HashMap<&str, Workflow>, categories: &Categories) -> Result<(), Box<dyn Error>> {
+    let findings = validate(workflows, categories);
+    if findings.iter().any(Finding::is_error) {
+        return Err(findings.iter().join("\n").into());
+    }
+    Ok(())
+}
+
+fn find_cycles(names: &[&str], workflows: &HashMap<&str, Workflow>) -> Vec<Finding> {
+    let mut done: HashSet<&str> = HashSet::new();
+    let mut findings = Vec::new();
+    for name in names {
+        visit(name, workflows, &mut Vec::new(), &mut done, &mut findings);
+    }
+    findings
+}
+
+fn visit<'a>(name: &'a str, workflows: &'a HashMap<&str, Workflow>, path: &mut Vec<&'a str>, done: &mut HashSet<&'a str>, findings: &mut Vec<Finding>) {
+    if done.contains(name) {
+        return;
+    }
+    if let Some(start) = path.iter().position(|visited| *visited == name) {
+        findings.push(Finding::Cycle {
+            workflows: path[start..].iter().map(|name| name.to_string()).collect(),
+        });
+        return;
+    }
+    let Some(workflow) = workflows.get(name) else {
+        return;
+    };
+    path.push(name);
+    for reference in workflow.references() {
+        visit(reference, workflows, path, done, findings);
+    }
+    path.pop();
+    done.insert(name);
+}
+
+pub(crate) fn find_reachable<'a>(start: &'a str, workflows: &'a HashMap<&str, Workflow>) -> HashSet<&'a str> {
+    let mut reachable = HashSet::from([start]);
+    let mut queue = vec![start];
+    while let Some(name) = queue.pop() {
+        for reference in workflows.get(name).map(Workflow::references).unwrap_or_default() {
+            if reachable.insert(reference) {
+                queue.push(reference);
+            }
+        }
+    }
+    reachable
+}
+
+fn find_unreachable_rules(name: &str, workflow: &Workflow, categories: &Categories) -> Vec<Finding> {
+    let (rules, default) = reachable_rules(workflow, categories);
+    let mut findings: Vec<Finding> = rules.iter()
+        .enumerate()
+        .filter(|(_, reachable)| !**reachable)
+        .map(|(idx, _)| Finding::UnreachableRule {
+            workflow: name.to_string(),
+            rule: idx,
+        })
+        .collect();
+    if !default {
+        findings.push(Finding::UnreachableDefault { workflow: name.to_string() });
+    }
+    findings
+}
+
+/// Whether each rule and the default can still match any part left over by the rules before them.
+pub(crate) fn reachable_rules(workflow: &Workflow, categories: &Categories) -> (Vec<bool>, bool) {
+    let mut remaining = vec![Entry::filled(categories, Range { start: 0, end: u64::MAX })];
+    let mut rules = Vec::new();
+    for rule in &workflow.rules {
+        let (inside, outside): (Vec<_>, Vec<_>) = remaining.iter()
+            .map(|entry| rule.split(entry))
+            .unzip();
+        rules.push(inside.iter().any(|parts| !parts.is_empty()));
+        remaining = outside.into_iter().flatten().collect();
+    }
+    (rules, !remaining.is_empty())
+}
+
+impl Finding {
+    /// Errors make solving impossible, everything else only points at dead parts of the input.
+    pub fn is_error(&self) -> bool {
+        matches!(self, Finding::MissingEntryPoint | Finding::UndefinedReference { .. } | Finding::Cycle { .. })
+    }
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Finding::MissingEntryPoint => write!(f, "Missing entry point 'in'"),
+            Finding::UndefinedReference { workflow, reference } => write!(f, "Workflow '{}' references undefined workflow '{}'", workflow, reference),
+            Finding::Cycle { workflows } => write!(f, "Cycle between workflows {} -> {}", workflows.join(" -> "), workflows[0]),
+            Finding::UnreachableWorkflow { workflow } => write!(f, "Workflow '{}' is never reached from 'in'", workflow),
+            Finding::UnreachableRule { workflow, rule } => write!(f, "Rule {} of workflow '{}' can never fire", rule, workflow),
+            Finding::UnreachableDefault { workflow } => write!(f, "Default of workflow '{}' can never be reached", workflow),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_workflows;
+
+    use super::*;
+
+    fn findings(block: &str) -> Vec<Finding> {
+        let mut categories = Categories::default();
+        let workflows = parse_workflows(block, &mut categories);
+        validate(&workflows, &categories)
+    }
+
+    #[test]
+    fn test_valid_example() {
+        let file = std::fs::read_to_string("example.txt").unwrap();
+        let (block, _) = file.split_once("\n\n").unwrap();
+
+        assert_eq!(findings(block), vec![]);
+    }
+
+    #[test]
+    fn test_reports_all_findings() {
+        let block = "start{x>10:a,R}\na{m<5:b,A}\nb{x>1:a,c}\nlost{A}";
+
+        assert_eq!(findings(block), vec![
+            Finding::MissingEntryPoint,
+            Finding::UndefinedReference { workflow: "b".to_string(), reference: "c".to_string() },
+            Finding::Cycle { workflows: vec!["a".to_string(), "b".to_string()] },
+        ]);
+    }
+
+    #[test]
+    fn test_check() {
+        let mut categories = Categories::default();
+        let workflows = parse_workflows("in{x>10:a,R}\na{m<5:in,A}", &mut categories);
+
+        assert_eq!(check(&workflows, &categories).unwrap_err().to_string(), "Cycle between workflows a -> in -> a");
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let block = "in{x>10:A,x>20:R,x<=10:R,A}\nlost{A}";
+
+        assert_eq!(findings(block), vec![
+            Finding::UnreachableWorkflow { workflow: "lost".to_string() },
+            Finding::UnreachableRule { workflow: "in".to_string(), rule: 1 },
+            Finding::UnreachableDefault { workflow: "in".to_string() },
+        ]);
+    }
+}
@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+use std::ops::{Index, IndexMut};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Workflow {
+    pub rules: Vec<Rule>,
+    pub default: Destination,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Rule {
+    pub expression: Expression,
+    pub destination: Destination,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expression {
+    Compare(Comparison),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Comparison {
+    pub category: Category,
+    pub condition: Condition,
+    pub value: u64,
+}
+
+/// Ratings of a part, indexed by the id of their category.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Entry<T> {
+    values: Vec<T>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Destination {
+    Accepted,
+    Rejected,
+    Ref(String),
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Condition {
+    Larger,
+    Lower,
+    LargerOrEqual,
+    LowerOrEqual,
+    Equal,
+    NotEqual,
+    /// Membership in `value..end`, the end is exclusive.
+    In(u64),
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, PartialOrd, Ord)]
+pub struct Category(pub usize);
+
+/// Interns category names in the order they are first seen.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Categories {
+    names: Vec<String>,
+}
+
+/// Anything a rule can be evaluated on, from a single part to a whole box of parts.
+pub trait Domain: Sized + Clone {
+    /// Returns the disjoint parts matching the condition (left) and the parts that do not (right).
+    fn split(&self, category: Category, condition: Condition, value: u64) -> (Vec<Self>, Vec<Self>);
+}
+
+impl Workflow {
+    pub fn create_branches<D: Domain>(&self, entry: &D) -> Vec<(D, &Destination)> {
+        let mut last_false = vec![entry.clone()];
+        let mut results: Vec<(D, &Destination)> = Vec::new();
+        for rule in &self.rules {
+            if last_false.is_empty() {
+                break;
+            }
+            let mut next_false = Vec::new();
+            for last in &last_false {
+                let (true_entries, false_entries) = rule.split(last);
+                results.extend(true_entries.into_iter().map(|entry| (entry, &rule.destination)));
+                next_false.extend(false_entries);
+            }
+            last_false = next_false;
+        }
+        results.extend(last_false.into_iter().map(|entry| (entry, &self.default)));
+        results
+    }
+
+    /// Names of all workflows this one sends parts to.
+    pub fn references(&self) -> Vec<&str> {
+        self.rules.iter()
+            .map(|rule| &rule.destination)
+            .chain([&self.default])
+            .filter_map(|destination| match destination {
+                Destination::Ref(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn destinations_mut(&mut self) -> impl Iterator<Item=&mut Destination> {
+        self.rules.iter_mut()
+            .map(|rule| &mut rule.destination)
+            .chain([&mut self.default])
+    }
+
+    pub fn evaluate(&self, entry: &Entry<u64>) -> &Destination {
+        self.rules.iter()
+            .find(|rule| rule.expression.holds(entry))
+            .map(|rule| &rule.destination)
+            .unwrap_or(&self.default)
+    }
+}
+
+/// Follows a part through the workflows until it is accepted or rejected.
+pub fn resolve_destination(entry: &Entry<u64>, destination: &Destination, workflows: &HashMap<&str, Workflow>) -> bool {
+    let mut destination = destination;
+    while let Destination::Ref(name) = destination {
+        destination = workflows.get(name.as_str()).expect("Could not find reference").evaluate(entry);
+    }
+    match destination {
+        Destination::Accepted => true,
+        Destination::Rejected => false,
+        Destination::Ref(_) => panic!("Something went terribly wrong")
+    }
+}
+
+impl Categories {
+    pub fn intern(&mut self, name: &str) -> Category {
+        self.get(name).unwrap_or_else(|| {
+            self.names.push(name.to_string());
+            Category(self.names.len() - 1)
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<Category> {
+        self.names.iter()
+            .position(|known| known == name)
+            .map(Category)
+    }
+
+    pub fn name(&self, category: Category) -> &str {
+        &self.names[category.0]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=Category> {
+        (0..self.names.len()).map(Category)
+    }
+}
+
+impl<T> Entry<T> {
+    pub fn new(values: Vec<T>) -> Self {
+        Self { values }
+    }
+
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+}
+
+impl<T: Clone> Entry<T> {
+    /// An entry rating every category with the same value.
+    pub fn filled(categories: &Categories, value: T) -> Self {
+        Self::new(vec![value; categories.len()])
+    }
+}
+
+impl<T> Index<Category> for Entry<T> {
+    type Output = T;
+
+    fn index(&self, i: Category) -> &Self::Output {
+        &self.values[i.0]
+    }
+}
+
+impl<T> IndexMut<Category> for Entry<T> {
+    fn index_mut(&mut self, i: Category) -> &mut Self::Output {
+        &mut self.values[i.0]
+    }
+}
+
+impl Entry<u64> {
+    pub fn sum(&self) -> u64 {
+        self.values.iter().sum()
+    }
+}
+
+impl Domain for Entry<u64> {
+    fn split(&self, category: Category, condition: Condition, value: u64) -> (Vec<Self>, Vec<Self>) {
+        if condition.holds(self[category], value) {
+            (vec![self.clone()], Vec::new())
+        } else {
+            (Vec::new(), vec![self.clone()])
+        }
+    }
+}
+
+impl Rule {
+    pub fn split<D: Domain>(&self, entry: &D) -> (Vec<D>, Vec<D>) {
+        self.expression.split(entry)
+    }
+}
+
+impl Expression {
+    pub fn compare(category: Category, condition: Condition, value: u64) -> Self {
+        Expression::Compare(Comparison {
+            category,
+            condition,
+            value,
+        })
+    }
+
+    pub fn holds(&self, entry: &Entry<u64>) -> bool {
+        match self {
+            Expression::Compare(comparison) => comparison.condition.holds(entry[comparison.category], comparison.value),
+            Expression::And(left, right) => left.holds(entry) && right.holds(entry),
+            Expression::Or(left, right) => left.holds(entry) || right.holds(entry),
+            Expression::Not(expression) => !expression.holds(entry),
+        }
+    }
+
+    /// Splits like a single comparison, every part of the result stays disjoint from all others.
+    pub fn split<D: Domain>(&self, entry: &D) -> (Vec<D>, Vec<D>) {
+        match self {
+            Expression::Compare(comparison) => entry.split(comparison.category, comparison.condition, comparison.value),
+            Expression::And(left, right) => {
+                let (left_inside, mut outside) = left.split(entry);
+                let mut inside = Vec::new();
+                for part in left_inside {
+                    let (right_inside, right_outside) = right.split(&part);
+                    inside.extend(right_inside);
+                    outside.extend(right_outside);
+                }
+                (inside, outside)
+            }
+            Expression::Or(left, right) => {
+                let (mut inside, left_outside) = left.split(entry);
+                let mut outside = Vec::new();
+                for part in left_outside {
+                    let (right_inside, right_outside) = right.split(&part);
+                    inside.extend(right_inside);
+                    outside.extend(right_outside);
+                }
+                (inside, outside)
+            }
+            Expression::Not(expression) => {
+                let (inside, outside) = expression.split(entry);
+                (outside, inside)
+            }
+        }
+    }
+}
+
+impl Condition {
+    pub fn holds(&self, rating: u64, value: u64) -> bool {
+        match self {
+            Condition::Larger => rating > value,
+            Condition::Lower => rating < value,
+            Condition::LargerOrEqual => rating >= value,
+            Condition::LowerOrEqual => rating <= value,
+            Condition::Equal => rating == value,
+            Condition::NotEqual => rating != value,
+            Condition::In(end) => (value..*end).contains(&rating),
+        }
+    }
+
+    /// Inclusive intervals of all ratings fulfilling the condition, sorted and disjoint.
+    pub fn intervals(&self, value: u64) -> Vec<(u64, u64)> {
+        let below = value.checked_sub(1).map(|last| (0, last));
+        let above = value.checked_add(1).map(|first| (first, u64::MAX));
+        match self {
+            Condition::Larger => above.into_iter().collect(),
+            Condition::Lower => below.into_iter().collect(),
+            Condition::LargerOrEqual => vec![(value, u64::MAX)],
+            Condition::LowerOrEqual => vec![(0, value)],
+            Condition::Equal => vec![(value, value)],
+            Condition::NotEqual => below.into_iter().chain(above).collect(),
+            Condition::In(end) => end.checked_sub(1)
+                .filter(|last| *last >= value)
+                .map(|last| (value, last))
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+impl Destination {
+    pub fn is_done(&self) -> bool {
+        match self {
+            Destination::Accepted => true,
+            Destination::Rejected => true,
+            Destination::Ref(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        let workflow = Workflow {
+            rules: vec![
+                Rule {
+                    expression: Expression::compare(Category(2), Condition::Larger, 1626),
+                    destination: Destination::Accepted,
+                },
+                Rule {
+                    expression: Expression::compare(Category(0), Condition::Lower, 2292),
+                    destination: Destination::Ref("ex".to_string()),
+                },
+            ],
+            default: Destination::Rejected,
+        };
+        let entry = Entry::new(vec![2000, 1, 1626, 1]);
+
+        assert_eq!(workflow.evaluate(&entry), &Destination::Ref("ex".to_string()));
+        assert_eq!(workflow.create_branches(&entry), vec![(entry, &Destination::Ref("ex".to_string()))]);
+    }
+
+    #[test]
+    fn test_compound_holds() {
+        let expression = Expression::And(
+            Box::new(Expression::compare(Category(0), Condition::Larger, 10)),
+            Box::new(Expression::Not(Box::new(Expression::compare(Category(1), Condition::Equal, 5)))),
+        );
+
+        assert!(expression.holds(&Entry::new(vec![11, 4])));
+        assert!(!expression.holds(&Entry::new(vec![11, 5])));
+        assert!(!expression.holds(&Entry::new(vec![10, 4])));
+    }
+
+    #[test]
+    fn test_condition_holds() {
+        let cases = vec![
+            (Condition::Larger, 5, [false, false, true]),
+            (Condition::Lower, 5, [true, false, false]),
+            (Condition::LargerOrEqual, 5, [false, true, true]),
+            (Condition::LowerOrEqual, 5, [true, true, false]),
+            (Condition::Equal, 5, [false, true, false]),
+            (Condition::NotEqual, 5, [true, false, true]),
+            (Condition::In(6), 5, [false, true, false]),
+            (Condition::In(5), 4, [true, false, false]),
+        ];
+
+        for (condition, value, expected) in cases {
+            assert_eq!([4, 5, 6].map(|rating| condition.holds(rating, value)), expected, "{:?} {}", condition, value);
+        }
+    }
+
+    #[test]
+    fn test_condition_intervals_edges() {
+        assert_eq!(Condition::Lower.intervals(0), vec![]);
+        assert_eq!(Condition::Larger.intervals(u64::MAX), vec![]);
+        assert_eq!(Condition::NotEqual.intervals(0), vec![(1, u64::MAX)]);
+        assert_eq!(Condition::In(3).intervals(3), vec![]);
+    }
+
+    #[test]
+    fn test_entry_sum() {
+        let entry = Entry::new(vec![787, 2655, 1222, 2876]);
+
+        assert_eq!(entry.sum(), 7540);
+    }
+
+    #[test]
+    fn test_intern_categories() {
+        let mut categories = Categories::default();
+
+        assert_eq!(categories.intern("x"), Category(0));
+        assert_eq!(categories.intern("weight"), Category(1));
+        assert_eq!(categories.intern("x"), Category(0));
+        assert_eq!(categories.iter().map(|category| categories.name(category)).collect::<Vec<_>>(), vec!["x", "weight"]);
+    }
+}
@@ -1,71 +1,44 @@
-#![feature(slice_take)]
-
 use std::collections::HashMap;
 use std::error::Error;
 
-use crate::parser::parse_workspace;
-use crate::types::{Destination, Entry, Range, Workflow};
-
-mod parser;
-mod types;
+use day19a::bounds::Bounds;
+use day19a::parser::{parse_entries, parse_workflows};
+use day19a::range::Rating;
+use day19a::region::Region;
+use day19a::validate;
+use day19a::workflow::{Categories, Workflow};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    shared::execute_solution("day19b/input.txt", solution).await
-}
-
-async fn solution(file: String) -> Result<u64, Box<dyn Error>> {
-    let parts: Vec<String> = file.split("\n\n").map(String::from).collect();
-    let workflows: HashMap<&str, Workflow> = parts.get(0)
-        .expect("Could not find workspace part")
-        .split("\n")
-        .map(|line| parse_workspace(line).expect("Could not parse workspace").1)
-        .collect();
-
-    let destination = Destination::Ref("in".to_string());
-    let branches = resolve_branches(&destination, &workflows);
-    let branches = branches.iter()
-        .map(|(entry, _)| entry)
-        .collect();
-    Ok(calculate_permutation(branches))
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    shared::execute_solution("day19b/input.txt", |file| solution(file, args)).await
 }
 
-fn resolve_branches<'a>(destination: &'a Destination, workflows: &'a HashMap<&str, Workflow>) -> Vec<(Entry, &'a Destination)> {
-    let mut found_all = false;
-    let start_entry = Entry {
-        x: Range { start: 1, end: 4000 },
-        m: Range { start: 1, end: 4000 },
-        a: Range { start: 1, end: 4000 },
-        s: Range { start: 1, end: 4000 },
-    };
-    let mut branches: Vec<(Entry, &Destination)> = vec![(start_entry, destination)];
-
-    while !found_all {
-        found_all = true;
-        branches = branches.iter()
-            .flat_map(|(rules, destination)| match destination {
-                Destination::Accepted => vec![(rules.clone(), *destination)],
-                Destination::Rejected => Vec::new(),
-                Destination::Ref(name) => {
-                    found_all = false;
-                    workflows.get(name.as_str())
-                        .expect("Could not resolve name")
-                        .create_branches(rules)
-                }
-            })
-            .collect();
+async fn solution(file: String, args: Vec<String>) -> Result<u128, Box<dyn Error>> {
+    let parts: Vec<&str> = file.split("\n\n").collect();
+    let (mut bounds, block) = Bounds::from_header(parts.first().expect("Could not find workspace part"))?;
+    bounds.apply_args(&args)?;
+    let mut categories = Categories::default();
+    let workflows = parse_workflows(block, &mut categories);
+    if let Some(entries) = parts.get(1) {
+        parse_entries(entries, &mut categories);
     }
+    validate::check(&workflows, &categories)?;
 
-    branches
+    match bounds.max() {
+        max if max <= u64::from(u16::MAX) => volume::<u16>(&workflows, &bounds, &categories),
+        max if max <= u64::from(u32::MAX) => volume::<u32>(&workflows, &bounds, &categories),
+        _ => volume::<u64>(&workflows, &bounds, &categories),
+    }
 }
 
-fn calculate_permutation(branches: Vec<&Entry>) -> u64 {
-    branches.iter()
-        .map(|entry| entry.permutation())
-        .sum()
+/// Accepted volume with the ratings stored in `T`, which has to fit the highest bound.
+fn volume<T: Rating>(workflows: &HashMap<&str, Workflow>, bounds: &Bounds, categories: &Categories) -> Result<u128, Box<dyn Error>> {
+    let start = bounds.entry::<T>(categories)?;
+    Region::accepted(workflows, "in", start).volume()
+        .ok_or_else(|| "Accepted volume exceeds u128".into())
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -73,8 +46,34 @@ mod tests {
     #[tokio::test]
     async fn test_solution() -> Result<(), Box<dyn Error>> {
         let lines = std::fs::read_to_string("example.txt")?;
-        let result = solution(lines).await?;
+        let result = solution(lines, Vec::new()).await?;
         assert_eq!(result, 167409079868000);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_custom_categories() -> Result<(), Box<dyn Error>> {
+        let lines = "in{weight>2000:A,size<11:heavy,R}\nheavy{weight>1000:A,R}\n\n{weight=1,size=2}".to_string();
+        let result = solution(lines, Vec::new()).await?;
+        assert_eq!(result, 2000 * 4000 + 1000 * 10);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_custom_bounds() -> Result<(), Box<dyn Error>> {
+        let lines = "bounds: *=1..=100000
+in{x>70000:A,R}".to_string();
+        assert_eq!(solution(lines.clone(), Vec::new()).await?, 30000);
+        let args = vec!["--bounds".to_string(), "x=1..=80000".to_string()];
+        assert_eq!(solution(lines, args).await?, 10000);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wide_bounds() -> Result<(), Box<dyn Error>> {
+        let lines = "bounds: *=1..=10,x=1..=10000000000
+in{x>5000000000:A,R}".to_string();
+        assert_eq!(solution(lines, Vec::new()).await?, 5000000000);
+        Ok(())
+    }
 }
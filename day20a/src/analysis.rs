@@ -0,0 +1,231 @@
+use std::fmt::{Display, Formatter};
+
+use petgraph::algo::tarjan_scc;
+use petgraph::graph::DiGraph;
+
+use crate::export::prefix;
+use crate::network::Network;
+use crate::types::{ModuleId, ModuleKind};
+
+/// Structure of a network as the puzzle inputs build it: binary counters off the broadcaster, each with a
+/// conjunction watching some of its bits, combined by a tree of conjunctions in front of the sink.
+#[derive(Debug, PartialEq)]
+pub struct Analysis {
+    /// Strongly connected components with a cycle, modules ordered by id.
+    pub components: Vec<Vec<String>>,
+    pub chains: Vec<Chain>,
+    pub tree: Option<Feeder>,
+}
+
+/// Flip-flops each feeding the next, starting at a destination of the broadcaster, lowest bit first.
+#[derive(Debug, PartialEq)]
+pub struct Chain {
+    pub flip_flops: Vec<String>,
+    /// The single conjunction the chain sends to, if there is one.
+    pub hub: Option<String>,
+    /// Count at which every bit sending to the hub is on, which is its period when the hub resets the counter.
+    pub value: Option<u64>,
+}
+
+/// A module and the modules sending to it, expanded as long as they are conjunctions.
+#[derive(Debug, PartialEq)]
+pub struct Feeder {
+    pub name: String,
+    pub inputs: Vec<Feeder>,
+}
+
+pub fn analyze(network: &Network, sink: &str) -> Analysis {
+    Analysis {
+        components: components(network),
+        chains: chains(network),
+        tree: network.id(sink).map(|sink| feeder(network, sink, &mut vec![sink])),
+    }
+}
+
+fn components(network: &Network) -> Vec<Vec<String>> {
+    let mut graph = DiGraph::<ModuleId, ()>::new();
+    let nodes: Vec<_> = network.ids().map(|id| graph.add_node(id)).collect();
+    for id in network.ids() {
+        for destination in network.destinations(id) {
+            graph.add_edge(nodes[id.0 as usize], nodes[destination.0 as usize], ());
+        }
+    }
+    let mut components: Vec<Vec<ModuleId>> = tarjan_scc(&graph).into_iter()
+        .map(|component| component.iter().map(|node| graph[*node]).collect::<Vec<_>>())
+        .filter(|component| component.len() > 1 || network.destinations(component[0]).contains(&component[0]))
+        .collect();
+    for component in &mut components {
+        component.sort();
+    }
+    components.sort();
+    components.iter()
+        .map(|component| component.iter().map(|id| network.name(*id).to_string()).collect())
+        .collect()
+}
+
+fn chains(network: &Network) -> Vec<Chain> {
+    let is_flip_flop = |id: &ModuleId| matches!(network.module(*id), Some(ModuleKind::FlipFlop(_)));
+    let broadcaster = network.id("broadcaster").expect("Could not find broadcaster");
+    network.destinations(broadcaster).iter()
+        .filter(|id| is_flip_flop(id))
+        .map(|start| {
+            let mut bits = vec![*start];
+            loop {
+                let next: Vec<&ModuleId> = network.destinations(*bits.last().unwrap()).iter()
+                    .filter(|id| is_flip_flop(id) && !bits.contains(id))
+                    .collect();
+                match next[..] {
+                    [next] => bits.push(*next),
+                    _ => break,
+                }
+            }
+            let mut hubs: Vec<ModuleId> = bits.iter()
+                .flat_map(|bit| network.destinations(*bit))
+                .filter(|id| matches!(network.module(**id), Some(ModuleKind::Conjunction(_))))
+                .copied()
+                .collect();
+            hubs.sort();
+            hubs.dedup();
+            let hub = match hubs[..] {
+                [hub] => Some(hub),
+                _ => None,
+            };
+            Chain {
+                flip_flops: bits.iter().map(|bit| network.name(*bit).to_string()).collect(),
+                hub: hub.map(|hub| network.name(hub).to_string()),
+                value: hub.map(|hub| bits.iter()
+                    .enumerate()
+                    .filter(|(_, bit)| network.destinations(**bit).contains(&hub))
+                    .map(|(idx, _)| 1 << idx)
+                    .sum()),
+            }
+        })
+        .collect()
+}
+
+/// `path` holds the modules above, so a cycle through conjunctions ends the expansion.
+fn feeder(network: &Network, id: ModuleId, path: &mut Vec<ModuleId>) -> Feeder {
+    let expand = path.len() == 1 || matches!(network.module(id), Some(ModuleKind::Conjunction(_)));
+    let mut inputs = Vec::new();
+    if expand {
+        for input in network.inputs(id) {
+            if path.contains(&input) {
+                continue;
+            }
+            path.push(input);
+            inputs.push(feeder(network, input, path));
+            path.pop();
+        }
+    }
+    Feeder { name: network.name(id).to_string(), inputs }
+}
+
+impl Feeder {
+    fn write(&self, f: &mut Formatter<'_>, network: &Network, depth: usize) -> std::fmt::Result {
+        let prefix = network.id(&self.name).and_then(|id| network.module(id)).map(prefix).unwrap_or_default();
+        writeln!(f, "{}{}{}", "  ".repeat(depth), prefix, self.name)?;
+        for input in &self.inputs {
+            input.write(f, network, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Analysis {
+    pub fn display<'a>(&'a self, network: &'a Network) -> AnalysisPrinter<'a> {
+        AnalysisPrinter { analysis: self, network }
+    }
+}
+
+pub struct AnalysisPrinter<'a> {
+    analysis: &'a Analysis,
+    network: &'a Network,
+}
+
+impl Display for AnalysisPrinter<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Cycles:")?;
+        for component in &self.analysis.components {
+            writeln!(f, "  {}", component.join(", "))?;
+        }
+        writeln!(f, "Counters:")?;
+        for chain in &self.analysis.chains {
+            write!(f, "  {}", chain.flip_flops.join(" -> "))?;
+            match (&chain.hub, chain.value) {
+                (Some(hub), Some(value)) => writeln!(f, " watched by &{} at {}", hub, value)?,
+                _ => writeln!(f)?,
+            }
+        }
+        writeln!(f, "Feeders:")?;
+        match &self.analysis.tree {
+            Some(tree) => tree.write(f, self.network, 1),
+            None => writeln!(f, "  none"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_machine;
+
+    use super::*;
+
+    /// Two counters in the shape of the puzzle inputs, their hubs reset them on 5 and 3.
+    const COUNTERS: &str = "broadcaster -> a1, b1
+%a1 -> a2, ha
+%a2 -> a3
+%a3 -> ha
+&ha -> a2, a1, ia
+&ia -> f
+%b1 -> b2, hb
+%b2 -> hb
+&hb -> b1, ib
+&ib -> f
+&f -> rx";
+
+    #[test]
+    fn test_analyze() {
+        let network = parse_machine(COUNTERS).unwrap();
+        let analysis = analyze(&network, "rx");
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(analysis.components, vec![names(&["a1", "a2", "a3", "ha"]), names(&["b1", "b2", "hb"])]);
+        assert_eq!(analysis.chains, vec![
+            Chain { flip_flops: names(&["a1", "a2", "a3"]), hub: Some("ha".to_string()), value: Some(5) },
+            Chain { flip_flops: names(&["b1", "b2"]), hub: Some("hb".to_string()), value: Some(3) },
+        ]);
+        assert_eq!(analysis.display(&network).to_string(), "Cycles:
+  a1, a2, a3, ha
+  b1, b2, hb
+Counters:
+  a1 -> a2 -> a3 watched by &ha at 5
+  b1 -> b2 watched by &hb at 3
+Feeders:
+  rx
+    &f
+      &ia
+        &ha
+          %a1
+          %a3
+      &ib
+        &hb
+          %b1
+          %b2
+");
+    }
+
+    #[test]
+    fn test_value_matches_simulation() {
+        let mut network = parse_machine(COUNTERS).unwrap();
+        let ha = network.id("ha").unwrap();
+        let mut hits = Vec::new();
+        while hits.len() < 3 {
+            let press = network.presses() + 1;
+            network.press(|pulse| if pulse.sender == ha && !pulse.high && hits.last() != Some(&press) {
+                hits.push(press);
+            });
+        }
+
+        assert_eq!(hits, vec![5, 10, 15]);
+    }
+}
@@ -0,0 +1,148 @@
+use std::error::Error;
+use std::fmt::Write;
+
+use crate::network::Network;
+use crate::types::{GateOp, ModuleId, ModuleKind};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Format {
+    Dot,
+    Mermaid,
+}
+
+/// Renders every module with its prefix and a style by kind, in the order the names were first seen.
+pub fn export(network: &Network, format: Format) -> String {
+    match format {
+        Format::Dot => export_dot(network),
+        Format::Mermaid => export_mermaid(network),
+    }
+}
+
+/// Reads `--export dot|mermaid` from the command line arguments.
+pub fn format_from_args(args: &[String]) -> Result<Option<Format>, Box<dyn Error>> {
+    match shared::flag_argument(args, "--export") {
+        None => Ok(None),
+        Some(Some("dot")) => Ok(Some(Format::Dot)),
+        Some(Some("mermaid")) => Ok(Some(Format::Mermaid)),
+        Some(_) => Err("Expected dot or mermaid after --export".into()),
+    }
+}
+
+/// Prefix as written in the puzzle input, empty for the broadcaster and sinks.
+pub fn prefix(module: &ModuleKind) -> String {
+    let prefix = match module {
+        ModuleKind::FlipFlop(_) => "%",
+        ModuleKind::Conjunction(_) => "&",
+        ModuleKind::Gate(gate) => match gate.op() {
+            GateOp::And => "*",
+            GateOp::Or => "|",
+            GateOp::Xor => "^",
+        },
+        ModuleKind::Delay(_) => "@",
+        ModuleKind::Counter(_) => "#",
+        ModuleKind::Custom(prefix, _) => return prefix.to_string(),
+        ModuleKind::Broadcaster(_) | ModuleKind::Sink(_) => "",
+    };
+    prefix.to_string()
+}
+
+/// Shape and fill color of a module in DOT.
+fn dot_style(module: &ModuleKind) -> &'static str {
+    match module {
+        ModuleKind::Broadcaster(_) => "shape=doubleoctagon, style=filled, fillcolor=gold",
+        ModuleKind::FlipFlop(_) => "shape=box, style=filled, fillcolor=lightblue",
+        ModuleKind::Conjunction(_) => "shape=ellipse, style=filled, fillcolor=orange",
+        ModuleKind::Sink(_) => "shape=doublecircle, style=filled, fillcolor=palegreen",
+        _ => "shape=hexagon",
+    }
+}
+
+fn class(module: &ModuleKind) -> &'static str {
+    match module {
+        ModuleKind::Broadcaster(_) => "broadcaster",
+        ModuleKind::FlipFlop(_) => "flipflop",
+        ModuleKind::Conjunction(_) => "conjunction",
+        ModuleKind::Sink(_) => "sink",
+        _ => "other",
+    }
+}
+
+fn export_dot(network: &Network) -> String {
+    let mut output = String::from("digraph network {\n");
+    for id in network.ids() {
+        if let Some(module) = network.module(id) {
+            writeln!(output, "    \"{}\" [label=\"{}{}\", {}];", network.name(id), prefix(module), network.name(id), dot_style(module)).unwrap();
+        }
+    }
+    for id in network.ids() {
+        for destination in network.destinations(id) {
+            writeln!(output, "    \"{}\" -> \"{}\";", network.name(id), network.name(*destination)).unwrap();
+        }
+    }
+    output.push('}');
+    output
+}
+
+/// Each kind of module gets its own class, declared at the end.
+fn export_mermaid(network: &Network) -> String {
+    let node = |id: ModuleId| shared::mermaid_id("m", network.name(id));
+    let mut output = String::from("flowchart LR\n");
+    for id in network.ids() {
+        if let Some(module) = network.module(id) {
+            writeln!(output, "    {}[\"{}{}\"]:::{}", node(id), prefix(module), network.name(id), class(module)).unwrap();
+        }
+    }
+    for id in network.ids() {
+        for destination in network.destinations(id) {
+            writeln!(output, "    {} --> {}", node(id), node(*destination)).unwrap();
+        }
+    }
+    output.push_str("    classDef broadcaster fill:#fd0,stroke:#960\n");
+    output.push_str("    classDef flipflop fill:#adf,stroke:#369\n");
+    output.push_str("    classDef conjunction fill:#fa3,stroke:#a50\n");
+    output.push_str("    classDef sink fill:#9f9,stroke:#393\n");
+    output.push_str("    classDef other fill:#ddd,stroke:#666");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_machine;
+
+    use super::*;
+
+    fn network() -> Network {
+        let content = std::fs::read_to_string("example2.txt").unwrap();
+        parse_machine(&content).unwrap()
+    }
+
+    #[test]
+    fn test_export_dot() {
+        assert_eq!(export(&network(), Format::Dot), [
+            "digraph network {",
+            "    \"broadcaster\" [label=\"broadcaster\", shape=doubleoctagon, style=filled, fillcolor=gold];",
+            "    \"a\" [label=\"%a\", shape=box, style=filled, fillcolor=lightblue];",
+            "    \"inv\" [label=\"&inv\", shape=ellipse, style=filled, fillcolor=orange];",
+            "    \"b\" [label=\"%b\", shape=box, style=filled, fillcolor=lightblue];",
+            "    \"con\" [label=\"&con\", shape=ellipse, style=filled, fillcolor=orange];",
+            "    \"output\" [label=\"output\", shape=doublecircle, style=filled, fillcolor=palegreen];",
+            "    \"broadcaster\" -> \"a\";",
+            "    \"a\" -> \"inv\";",
+            "    \"a\" -> \"con\";",
+            "    \"inv\" -> \"b\";",
+            "    \"b\" -> \"con\";",
+            "    \"con\" -> \"output\";",
+            "}",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_export_mermaid() {
+        let output = export(&network(), Format::Mermaid);
+
+        assert!(output.starts_with("flowchart LR\n    m_broadcaster[\"broadcaster\"]:::broadcaster\n    m_a[\"%a\"]:::flipflop\n"));
+        assert!(output.contains("    m_output[\"output\"]:::sink\n    m_broadcaster --> m_a\n"));
+        assert_eq!(format_from_args(&["--export".to_string(), "mermaid".to_string()]).unwrap(), Some(Format::Mermaid));
+        assert!(format_from_args(&["--export".to_string()]).is_err());
+    }
+}
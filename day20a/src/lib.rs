@@ -0,0 +1,9 @@
+pub mod analysis;
+pub mod export;
+pub mod network;
+pub mod parser;
+pub mod registry;
+pub mod repl;
+pub mod trace;
+pub mod types;
+pub mod validate;
@@ -1,18 +1,43 @@
-#![feature(slice_take)]
+#![feature(test)]
+extern crate test;
 
-use std::cell::RefCell;
-use std::collections::HashMap;
 use std::error::Error;
-use std::rc::Rc;
 
-use crate::parser::parse_machine;
-use crate::types::Module;
-
-mod parser;
-mod types;
+use day20a::analysis::analyze;
+use day20a::export::{export, format_from_args};
+use day20a::parser::parse_machine;
+use day20a::repl::Session;
+use day20a::trace::TraceOptions;
+use day20a::validate::validate;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(trace) = TraceOptions::from_args(&args)? {
+        let mut network = parse_machine(&std::fs::read_to_string("day20a/input.txt")?)?;
+        trace.run(&mut network, std::io::stdout().lock());
+        return Ok(());
+    }
+    if let Some(format) = format_from_args(&args)? {
+        println!("{}", export(&parse_machine(&std::fs::read_to_string("day20a/input.txt")?)?, format));
+        return Ok(());
+    }
+    if let Some(sink) = shared::flag_argument(&args, "--analyze") {
+        let sink = sink.unwrap_or("rx");
+        let network = parse_machine(&std::fs::read_to_string("day20a/input.txt")?)?;
+        print!("{}", analyze(&network, sink).display(&network));
+        return Ok(());
+    }
+    if shared::has_flag(&args, "--interactive") {
+        let mut session = Session::new(parse_machine(&std::fs::read_to_string("day20a/input.txt")?)?);
+        return session.run(std::io::stdin().lock(), std::io::stdout().lock());
+    }
+    if shared::has_flag(&args, "--validate") {
+        for finding in validate(&parse_machine(&std::fs::read_to_string("day20a/input.txt")?)?) {
+            println!("{}", finding);
+        }
+        return Ok(());
+    }
     shared::execute_solution("day20a/input.txt", solution).await
 }
 
@@ -21,32 +46,9 @@ async fn solution(content: String) -> Result<u64, Box<dyn Error>> {
 }
 
 fn run(content: &str) -> Result<u64, Box<dyn Error>> {
-    let modules = parse_machine(content)?;
-
-    let mut sum: (u64, u64) = (0, 0);
-    for _ in 0..1000 {
-        let result = forward_signals(&vec![String::from("broadcaster")], &modules, false, "broadcaster");
-        sum.0 += result.0;
-        sum.1 += result.1;
-    }
-
-    Ok(sum.0 * sum.1)
-}
-
-fn forward_signals(send_to: &Vec<String>, modules: &HashMap<String, Rc<RefCell<dyn Module>>>, input: bool, sender: &str) -> (u64, u64) {
-    let mut count = (send_to.len() as u64 * input as u64, send_to.len() as u64 * !input as u64);
-    send_to.iter()
-        .filter_map(|module_name| modules.get(module_name).map(|module| (module_name, module)))
-        .filter_map(|(module_name, module)| module.borrow_mut().update_state(input, String::from(sender)).map(|output| (module_name, module, output)))
-        .collect::<Vec<_>>()
-        .iter()
-        .for_each(|(module_name, module, output)| {
-            let destinations = module.borrow().get_destinations().clone();
-            let result = forward_signals(&destinations, modules, *output, module_name);
-            count.0 += result.0;
-            count.1 += result.1;
-        });
-    count
+    let mut network = parse_machine(content)?;
+    let (low, high) = network.count_pulses(1000);
+    Ok(low * high)
 }
 
 
@@ -54,6 +56,10 @@ fn forward_signals(send_to: &Vec<String>, modules: &HashMap<String, Rc<RefCell<d
 mod tests {
     use std::error::Error;
 
+    use test::Bencher;
+
+    use day20a::parser::parse_machine;
+
     use crate::solution;
 
     #[tokio::test]
@@ -71,4 +77,12 @@ mod tests {
         assert_eq!(result, 11687500);
         Ok(())
     }
+
+    #[bench]
+    fn bench_press(b: &mut Bencher) {
+        let content = std::fs::read_to_string("input.txt").unwrap();
+        let mut network = parse_machine(&content).unwrap();
+
+        b.iter(|| network.press(|_| {}));
+    }
 }
@@ -0,0 +1,390 @@
+use std::collections::{HashMap, VecDeque};
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::hash::{Hash, Hasher};
+
+use crate::types::{Module, ModuleId, ModuleKind, Names, Received};
+
+/// Presses `count_pulses` keeps states for while looking for a cycle, a few KB each on the puzzle input.
+const CYCLE_SEARCH_PRESSES: u64 = 10_000;
+
+/// A single pulse on its way from one module to another, `high` is the level it carries.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct Pulse {
+    pub sender: ModuleId,
+    pub receiver: ModuleId,
+    pub high: bool,
+}
+
+/// Memory of every module together with the pulses still queued.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Snapshot {
+    states: Vec<Vec<u64>>,
+    /// Number of pulses each sink had recorded.
+    recorded: Vec<usize>,
+    queue: VecDeque<Pulse>,
+    presses: u64,
+}
+
+/// Modules stored by id, delivering pulses strictly in the order they were sent, one at a time.
+pub struct Network {
+    names: Names,
+    /// `None` only for the button.
+    modules: Vec<Option<ModuleKind>>,
+    destinations: Vec<Vec<ModuleId>>,
+    button: ModuleId,
+    broadcaster: ModuleId,
+    queue: VecDeque<Pulse>,
+    presses: u64,
+}
+
+impl Network {
+    /// Takes modules and destinations indexed by id, the inputs of all modules have to be registered already.
+    pub fn new(mut names: Names, mut modules: Vec<Option<ModuleKind>>, mut destinations: Vec<Vec<ModuleId>>) -> Result<Self, Box<dyn Error>> {
+        let broadcaster = names.get("broadcaster").ok_or("Could not find broadcaster")?;
+        let button = names.intern("button");
+        modules.resize(names.len(), None);
+        destinations.resize(names.len(), Vec::new());
+        Ok(Self {
+            names,
+            modules,
+            destinations,
+            button,
+            broadcaster,
+            queue: VecDeque::new(),
+            presses: 0,
+        })
+    }
+
+    /// Presses the button and delivers pulses until the network settles, handing each delivered pulse to `on_pulse`.
+    pub fn press(&mut self, mut on_pulse: impl FnMut(&Pulse)) {
+        self.queue_button();
+        while let Some(pulse) = self.step() {
+            on_pulse(&pulse);
+        }
+    }
+
+    /// Queues the low pulse the button sends to the broadcaster without delivering it.
+    pub fn queue_button(&mut self) {
+        self.presses += 1;
+        self.queue.push_back(Pulse {
+            sender: self.button,
+            receiver: self.broadcaster,
+            high: false,
+        });
+    }
+
+    /// Delivers the oldest queued pulse and queues everything the receiver sends in response.
+    pub fn step(&mut self) -> Option<Pulse> {
+        let pulse = self.queue.pop_front()?;
+        let receiver = pulse.receiver.0 as usize;
+        if let Some(ModuleKind::Sink(sink)) = &mut self.modules[receiver] {
+            sink.record(Received { press: self.presses, sender: pulse.sender, high: pulse.high });
+        } else if let Some(module) = &mut self.modules[receiver] {
+            if let Some(high) = module.update_state(pulse.high, pulse.sender) {
+                self.queue.extend(self.destinations[receiver].iter().map(|destination| Pulse {
+                    sender: pulse.receiver,
+                    receiver: *destination,
+                    high,
+                }));
+            }
+        }
+        Some(pulse)
+    }
+
+    /// Counts low and high pulses over `presses` presses. Once the network returns to a state it was in before,
+    /// the remaining presses skip whole cycles, so the count stays cheap for huge numbers on periodic networks.
+    ///
+    /// Only cycles closing within the first `CYCLE_SEARCH_PRESSES` presses are found, after that every press is
+    /// simulated without keeping its state. Sinks do not record the presses skipped over.
+    pub fn count_pulses(&mut self, presses: u64) -> (u64, u64) {
+        self.count_pulses_within(presses, CYCLE_SEARCH_PRESSES)
+    }
+
+    fn count_pulses_within(&mut self, presses: u64, search: u64) -> (u64, u64) {
+        let tracked = presses.min(search) as usize;
+        let mut seen: HashMap<Vec<Vec<u64>>, usize> = HashMap::new();
+        let mut history = vec![self.snapshot()];
+        let mut counts = vec![(0, 0)];
+        for press in 0..tracked {
+            if let Some(start) = seen.insert(self.snapshot().states, press) {
+                let length = press - start;
+                let (low, high) = (counts[press].0 - counts[start].0, counts[press].1 - counts[start].1);
+                let remaining = presses - press as u64;
+                let (cycles, rest) = (remaining / length as u64, (remaining % length as u64) as usize);
+                let end = start + rest;
+                self.restore(&history[end]);
+                self.presses = history[0].presses + presses;
+                return (
+                    counts[press].0 + cycles * low + counts[end].0 - counts[start].0,
+                    counts[press].1 + cycles * high + counts[end].1 - counts[start].1,
+                );
+            }
+            let (mut low, mut high) = counts[press];
+            self.press(|pulse| if pulse.high {
+                high += 1;
+            } else {
+                low += 1;
+            });
+            counts.push((low, high));
+            history.push(self.snapshot());
+        }
+        drop((seen, history));
+
+        let (mut low, mut high) = counts[tracked];
+        for _ in tracked as u64..presses {
+            self.press(|pulse| if pulse.high {
+                high += 1;
+            } else {
+                low += 1;
+            });
+        }
+        (low, high)
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            states: self.modules.iter()
+                .map(|module| module.as_ref().map(Module::state).unwrap_or_default())
+                .collect(),
+            recorded: self.modules.iter()
+                .map(|module| match module {
+                    Some(ModuleKind::Sink(sink)) => sink.received().len(),
+                    _ => 0,
+                })
+                .collect(),
+            queue: self.queue.clone(),
+            presses: self.presses,
+        }
+    }
+
+    /// Also drops whatever sinks recorded after the snapshot was taken.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        for ((module, state), recorded) in self.modules.iter_mut().zip(&snapshot.states).zip(&snapshot.recorded) {
+            match module {
+                Some(ModuleKind::Sink(sink)) => sink.truncate(*recorded),
+                Some(module) => module.restore(state),
+                None => {}
+            }
+        }
+        self.queue = snapshot.queue.clone();
+        self.presses = snapshot.presses;
+    }
+
+    /// Hash of the memory of all modules, equal whenever the network is in the same configuration.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.snapshot().states.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Whether every pulse has been delivered, so the button may be pressed again.
+    pub fn is_settled(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn presses(&self) -> u64 {
+        self.presses
+    }
+
+    pub fn id(&self, name: &str) -> Option<ModuleId> {
+        self.names.get(name)
+    }
+
+    pub fn name(&self, id: ModuleId) -> &str {
+        self.names.name(id)
+    }
+
+    pub fn module(&self, id: ModuleId) -> Option<&ModuleKind> {
+        self.modules[id.0 as usize].as_ref()
+    }
+
+    /// Pulses received by `id` so far, `None` if it is not a sink.
+    pub fn received(&self, id: ModuleId) -> Option<&[Received]> {
+        match self.module(id) {
+            Some(ModuleKind::Sink(sink)) => Some(sink.received()),
+            _ => None,
+        }
+    }
+
+    /// Forgets what the sink `id` received so far, so long simulations do not keep every pulse.
+    pub fn clear_received(&mut self, id: ModuleId) {
+        if let Some(ModuleKind::Sink(sink)) = self.modules[id.0 as usize].as_mut() {
+            sink.truncate(0);
+        }
+    }
+
+    /// Ids of all sinks, the names that are only used as destinations.
+    pub fn sinks(&self) -> impl Iterator<Item=ModuleId> + '_ {
+        self.ids().filter(|id| matches!(self.module(*id), Some(ModuleKind::Sink(_))))
+    }
+
+    pub fn destinations(&self, id: ModuleId) -> &[ModuleId] {
+        &self.destinations[id.0 as usize]
+    }
+
+    /// Modules sending pulses to `id`.
+    pub fn inputs(&self, id: ModuleId) -> Vec<ModuleId> {
+        self.ids()
+            .filter(|input| self.destinations(*input).contains(&id))
+            .collect()
+    }
+
+    /// Ids of all modules and destinations, including the button.
+    pub fn ids(&self) -> impl Iterator<Item=ModuleId> {
+        (0..self.names.len() as u32).map(ModuleId)
+    }
+
+    /// Puzzle notation of a pulse, e.g. `broadcaster -low-> a`.
+    pub fn describe(&self, pulse: &Pulse) -> String {
+        let level = if pulse.high { "high" } else { "low" };
+        format!("{} -{}-> {}", self.name(pulse.sender), level, self.name(pulse.receiver))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_machine;
+    use crate::types::{Broadcaster, Conjunction};
+
+    use super::*;
+
+    fn pulses(network: &mut Network) -> Vec<String> {
+        let mut pulses = Vec::new();
+        network.press(|pulse| pulses.push(*pulse));
+        pulses.iter().map(|pulse| network.describe(pulse)).collect()
+    }
+
+    #[test]
+    fn test_fifo_order() {
+        let content = std::fs::read_to_string("example1.txt").unwrap();
+        let mut network = parse_machine(&content).unwrap();
+
+        assert_eq!(pulses(&mut network), vec![
+            "button -low-> broadcaster",
+            "broadcaster -low-> a",
+            "broadcaster -low-> b",
+            "broadcaster -low-> c",
+            "a -high-> b",
+            "b -high-> c",
+            "c -high-> inv",
+            "inv -low-> a",
+            "a -low-> b",
+            "b -low-> c",
+            "c -low-> inv",
+            "inv -high-> a",
+        ]);
+    }
+
+    #[test]
+    fn test_step() {
+        let content = std::fs::read_to_string("example2.txt").unwrap();
+        let mut network = parse_machine(&content).unwrap();
+        network.queue_button();
+
+        assert_eq!(network.step().map(|pulse| network.describe(&pulse)), Some("button -low-> broadcaster".to_string()));
+        assert_eq!(network.step().map(|pulse| network.describe(&pulse)), Some("broadcaster -low-> a".to_string()));
+        assert_eq!(network.presses(), 1);
+    }
+
+    #[test]
+    fn test_sink_records_pulses() {
+        let content = std::fs::read_to_string("example2.txt").unwrap();
+        let mut network = parse_machine(&content).unwrap();
+        network.press(|_| {});
+        network.press(|_| {});
+        let output = network.id("output").unwrap();
+        let con = network.id("con").unwrap();
+
+        assert_eq!(network.sinks().collect::<Vec<_>>(), vec![output]);
+        assert_eq!(network.received(output).unwrap(), [
+            Received { press: 1, sender: con, high: true },
+            Received { press: 1, sender: con, high: false },
+            Received { press: 2, sender: con, high: true },
+        ]);
+        assert_eq!(network.received(con), None);
+    }
+
+    #[test]
+    fn test_restore_drops_later_recordings() {
+        let content = std::fs::read_to_string("example2.txt").unwrap();
+        let mut network = parse_machine(&content).unwrap();
+        let output = network.id("output").unwrap();
+        network.press(|_| {});
+        let snapshot = network.snapshot();
+        network.press(|_| {});
+        let expected = network.received(output).unwrap().to_vec();
+
+        network.restore(&snapshot);
+        assert!(network.received(output).unwrap().iter().all(|received| received.press == 1));
+        network.press(|_| {});
+        assert_eq!(network.received(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let content = std::fs::read_to_string("example2.txt").unwrap();
+        let mut network = parse_machine(&content).unwrap();
+        network.press(|_| {});
+        let snapshot = network.snapshot();
+        let hash = network.state_hash();
+        let expected = pulses(&mut network);
+
+        network.restore(&snapshot);
+        assert_eq!(network.state_hash(), hash);
+        assert_eq!(pulses(&mut network), expected);
+        assert_eq!(network.presses(), 2);
+    }
+
+    #[test]
+    fn test_count_pulses_skips_cycles() {
+        let content = std::fs::read_to_string("example2.txt").unwrap();
+        let mut simulated = parse_machine(&content).unwrap();
+        let mut expected = (0, 0);
+        for _ in 0..1003 {
+            simulated.press(|pulse| if pulse.high { expected.1 += 1 } else { expected.0 += 1 });
+        }
+        let mut network = parse_machine(&content).unwrap();
+
+        assert_eq!(network.count_pulses(1003), expected);
+        assert_eq!(network.state_hash(), simulated.state_hash());
+        assert_eq!(network.presses(), 1003);
+
+        let mut network = parse_machine(&content).unwrap();
+        assert_eq!(network.count_pulses_within(1003, 3), expected);
+        assert_eq!(network.state_hash(), simulated.state_hash());
+
+        let content = std::fs::read_to_string("example1.txt").unwrap();
+        let mut network = parse_machine(&content).unwrap();
+        assert_eq!(network.count_pulses(1_000_000_000), (8_000_000_000, 4_000_000_000));
+    }
+
+    #[test]
+    fn test_deep_chain() {
+        let mut names = Names::default();
+        let ids: Vec<ModuleId> = ["broadcaster".to_string()].into_iter()
+            .chain((0..=100_000).map(|idx| format!("m{}", idx)))
+            .map(|name| names.intern(&name))
+            .collect();
+        let modules = ids.iter()
+            .enumerate()
+            .map(|(idx, _)| Some(match idx {
+                0 => ModuleKind::Broadcaster(Broadcaster),
+                _ => {
+                    let mut inverter = Conjunction::default();
+                    inverter.register_input(ids[idx - 1]);
+                    ModuleKind::Conjunction(inverter)
+                }
+            }))
+            .collect();
+        let destinations = ids.iter()
+            .skip(1)
+            .map(|id| vec![*id])
+            .collect();
+        let mut network = Network::new(names, modules, destinations).unwrap();
+        let mut count = 0;
+        network.press(|_| count += 1);
+
+        assert_eq!(count, 100_002);
+    }
+}
@@ -1,51 +1,71 @@
-use std::cell::RefCell;
-use std::collections::HashMap;
 use std::error::Error;
-use std::rc::Rc;
 
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::alphanumeric1;
-use nom::combinator::peek;
+use nom::character::complete::{alphanumeric1, anychar};
+use nom::combinator::{map, peek, verify};
 use nom::error::context;
 use nom::IResult;
 use nom::multi::separated_list0;
 use nom::sequence::{terminated, tuple};
 
-use crate::types::{Broadcaster, Conjunction, FlipFlop, Module};
+use crate::network::Network;
+use crate::registry::Registry;
+use crate::types::{Broadcaster, Module, ModuleId, ModuleKind, Names, Sink};
 
-pub fn parse_machine(content: &str) -> Result<HashMap<String, Rc<RefCell<dyn Module>>>, Box<dyn Error>> {
-    let modules: HashMap<String, Rc<RefCell<dyn Module>>> = content.split("\n")
-        .map(|line| parse_item(line).expect("Could not parse line"))
-        .map(|(name, item)| (String::from(name), create_module(item)))
-        .collect();
+pub fn parse_machine(content: &str) -> Result<Network, Box<dyn Error>> {
+    parse_machine_with(content, &Registry::default())
+}
+
+/// Parses a machine whose module kinds are looked up in `registry`.
+pub fn parse_machine_with(content: &str, registry: &Registry) -> Result<Network, Box<dyn Error>> {
+    let items = content.split("\n")
+        .filter(|line| !line.is_empty())
+        .map(|line| parse_item(line).map_err(|err| format!("Could not parse line {}: {}", line, err)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut names = Names::default();
+    for (name, _) in &items {
+        if names.get(name).is_some() {
+            return Err(format!("Module {} is defined more than once", name).into());
+        }
+        names.intern(name);
+    }
+    let mut modules: Vec<Option<ModuleKind>> = vec![None; items.len()];
+    let mut destinations: Vec<Vec<ModuleId>> = vec![Vec::new(); items.len()];
+    for (name, item) in items {
+        let id = names.intern(name).0 as usize;
+        destinations[id] = item.destinations.iter().map(|destination| names.intern(destination)).collect();
+        modules[id] = Some(create_module(name, item.parsed_type, registry)?);
+    }
+    // Names without a line of their own are only destinations.
+    modules.resize_with(names.len(), || Some(ModuleKind::Sink(Sink::default())));
 
-    register_source("broadcaster", "", &modules);
+    register_inputs(&mut modules, &destinations);
 
-    Ok(modules)
+    Network::new(names, modules, destinations)
 }
 
-fn register_source(name: &str, source: &str, modules: &HashMap<String, Rc<RefCell<dyn Module>>>) {
-    if let Some(module) = modules.get(name) {
-        if let Ok(mut module_ref) = module.try_borrow_mut() {
-            module_ref.register_input(String::from(source));
-            drop(module_ref);
-            for dest in module.borrow().get_destinations().clone() {
-                register_source(&dest, name, modules);
+/// Registers every edge of the graph as input of its destination, whether the sender is reachable or not.
+fn register_inputs(modules: &mut [Option<ModuleKind>], destinations: &[Vec<ModuleId>]) {
+    for (id, destinations) in destinations.iter().enumerate() {
+        for destination in destinations {
+            if let Some(module) = &mut modules[destination.0 as usize] {
+                module.register_input(ModuleId(id as u32));
             }
         }
     }
 }
 
-fn create_module(item: ParsedItem) -> Rc<RefCell<dyn Module>> {
-    match item.parsed_type {
-        ParsedType::Broadcaster => Rc::new(RefCell::new(Broadcaster::from(item.destinations))),
-        ParsedType::FlipFlop => Rc::new(RefCell::new(FlipFlop::from(item.destinations))),
-        ParsedType::Conjunction => Rc::new(RefCell::new(Conjunction::from(item.destinations))),
+fn create_module(name: &str, parsed_type: ParsedType, registry: &Registry) -> Result<ModuleKind, Box<dyn Error>> {
+    match parsed_type {
+        ParsedType::Broadcaster => Ok(ModuleKind::Broadcaster(Broadcaster)),
+        ParsedType::Prefix(prefix) => registry.create(prefix)
+            .ok_or_else(|| format!("Unknown module prefix {} in front of {}", prefix, name).into()),
     }
 }
 
-fn parse_item(input: &str) -> IResult<&str, ParsedItem> {
+fn parse_item(input: &str) -> IResult<&str, ParsedItem<'_>> {
     context("line", tuple((parse_type, terminated(alphanumeric1, tag(" -> ")), separated_list0(tag(", "), alphanumeric1))))(input)
         .map(|(_, res)| {
             let (parsed_type, name, destinations) = res;
@@ -56,9 +76,12 @@ fn parse_item(input: &str) -> IResult<&str, ParsedItem> {
         })
 }
 
+/// Any character that cannot start a name is a prefix, whether a kind is registered for it is checked later.
 fn parse_type(input: &str) -> IResult<&str, ParsedType> {
-    context("type", alt((peek(tag("broadcaster")), tag("&"), tag("%"))))(input)
-        .map(|(rest, val)| (rest, val.into()))
+    context("type", alt((
+        map(peek(tag("broadcaster")), |_| ParsedType::Broadcaster),
+        map(verify(anychar, |prefix| !prefix.is_alphanumeric() && !prefix.is_whitespace()), ParsedType::Prefix),
+    )))(input)
 }
 
 #[derive(Debug, PartialEq)]
@@ -70,19 +93,7 @@ struct ParsedItem<'a> {
 #[derive(Debug, PartialEq)]
 enum ParsedType {
     Broadcaster,
-    FlipFlop,
-    Conjunction,
-}
-
-impl From<&str> for ParsedType {
-    fn from(i: &str) -> Self {
-        match i.to_lowercase().as_str() {
-            "broadcaster" => ParsedType::Broadcaster,
-            "%" => ParsedType::FlipFlop,
-            "&" => ParsedType::Conjunction,
-            _ => unimplemented!("no other schemes supported"),
-        }
-    }
+    Prefix(char),
 }
 
 #[cfg(test)]
@@ -95,11 +106,11 @@ mod tests {
     fn test_parse_type() {
         assert_eq!(
             parse_type("%test -> a, b"),
-            Ok(("test -> a, b", ParsedType::FlipFlop))
+            Ok(("test -> a, b", ParsedType::Prefix('%')))
         );
         assert_eq!(
             parse_type("&test -> a, b"),
-            Ok(("test -> a, b", ParsedType::Conjunction))
+            Ok(("test -> a, b", ParsedType::Prefix('&')))
         );
         assert_eq!(
             parse_type("broadcaster -> a, b"),
@@ -109,11 +120,27 @@ mod tests {
             parse_type("test -> a, b"),
             Err(nom::Err::Error(nom::error::Error {
                 input: "test -> a, b",
-                code: ErrorKind::Tag,
+                code: ErrorKind::Verify,
             }))
         );
     }
 
+    #[test]
+    fn test_registers_unreachable_inputs() {
+        let mut network = parse_machine("broadcaster -> a\n%a -> con\n&con -> out\n%lost -> con").unwrap();
+        let mut pulses = Vec::new();
+        network.press(|pulse| pulses.push(*pulse));
+
+        assert_eq!(network.describe(pulses.last().unwrap()), "con -high-> out");
+    }
+
+    #[test]
+    fn test_duplicate_definition() {
+        let error = parse_machine("broadcaster -> a\n%a -> b\n&a -> c").err().unwrap();
+
+        assert_eq!(error.to_string(), "Module a is defined more than once");
+    }
+
     #[test]
     fn test_parse_item() {
         assert_eq!(
@@ -126,14 +153,14 @@ mod tests {
         assert_eq!(
             parse_item("%a -> bc"),
             Ok(("a", ParsedItem {
-                parsed_type: ParsedType::FlipFlop,
+                parsed_type: ParsedType::Prefix('%'),
                 destinations: vec!["bc"],
             }))
         );
         assert_eq!(
             parse_item("&inv -> a, bc"),
             Ok(("inv", ParsedItem {
-                parsed_type: ParsedType::Conjunction,
+                parsed_type: ParsedType::Prefix('&'),
                 destinations: vec!["a", "bc"],
             }))
         );
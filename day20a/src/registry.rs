@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use crate::types::{Conjunction, Counter, CustomModule, Delay, FlipFlop, Gate, GateOp, ModuleKind};
+
+/// Creates modules by the prefix character in front of their name.
+pub struct Registry {
+    kinds: HashMap<char, Box<dyn Fn() -> ModuleKind>>,
+}
+
+impl Registry {
+    /// A registry without any kinds, not even flip-flops and conjunctions.
+    pub fn empty() -> Self {
+        Self { kinds: HashMap::new() }
+    }
+
+    /// Adds a kind for `prefix`, replacing the one registered before.
+    pub fn register(&mut self, prefix: char, create: impl Fn() -> ModuleKind + 'static) -> &mut Self {
+        self.kinds.insert(prefix, Box::new(create));
+        self
+    }
+
+    /// Adds a kind implemented outside this crate, which keeps `prefix` so it can be written back.
+    pub fn register_custom(&mut self, prefix: char, create: impl Fn() -> Box<dyn CustomModule> + 'static) -> &mut Self {
+        self.register(prefix, move || ModuleKind::Custom(prefix, create()))
+    }
+
+    /// A fresh module for `prefix`, `None` if no kind is registered for it.
+    pub fn create(&self, prefix: char) -> Option<ModuleKind> {
+        self.kinds.get(&prefix).map(|create| create())
+    }
+}
+
+/// The puzzle's `%` and `&`, plus `*`, `|` and `^` gates, the `@` delay and `#` as a decade counter.
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register('%', || ModuleKind::FlipFlop(FlipFlop::default()))
+            .register('&', || ModuleKind::Conjunction(Conjunction::default()))
+            .register('*', || ModuleKind::Gate(Gate::new(GateOp::And)))
+            .register('|', || ModuleKind::Gate(Gate::new(GateOp::Or)))
+            .register('^', || ModuleKind::Gate(Gate::new(GateOp::Xor)))
+            .register('@', || ModuleKind::Delay(Delay::default()))
+            .register('#', || ModuleKind::Counter(Counter::new(10)));
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::prefix;
+    use crate::parser::{parse_machine, parse_machine_with};
+    use crate::types::{Module, ModuleId};
+
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    struct Inverter;
+
+    impl Module for Inverter {
+        fn update_state(&mut self, input: bool, _sender: ModuleId) -> Option<bool> {
+            Some(!input)
+        }
+
+        fn register_input(&mut self, _input: ModuleId) {}
+
+        fn state(&self) -> Vec<u64> {
+            Vec::new()
+        }
+
+        fn restore(&mut self, _state: &[u64]) {}
+    }
+
+    /// Sends high on every third pulse it receives and nothing otherwise.
+    #[derive(Debug, Clone, Default)]
+    struct Third {
+        received: u64,
+    }
+
+    impl Module for Third {
+        fn update_state(&mut self, _input: bool, _sender: ModuleId) -> Option<bool> {
+            self.received = (self.received + 1) % 3;
+            (self.received == 0).then_some(true)
+        }
+
+        fn register_input(&mut self, _input: ModuleId) {}
+
+        fn state(&self) -> Vec<u64> {
+            vec![self.received]
+        }
+
+        fn restore(&mut self, state: &[u64]) {
+            self.received = state[0];
+        }
+    }
+
+    #[test]
+    fn test_custom_kinds() {
+        let mut registry = Registry::default();
+        registry.register_custom('!', || Box::new(Inverter))
+            .register('#', || ModuleKind::Counter(Counter::new(2)));
+        let mut network = parse_machine_with("broadcaster -> n, c\n!n -> out\n#c -> out", &registry).unwrap();
+        let mut pulses = Vec::new();
+        network.press(|pulse| pulses.push(*pulse));
+        network.press(|pulse| pulses.push(*pulse));
+
+        assert_eq!(pulses.iter().filter(|pulse| network.name(pulse.receiver) == "out").map(|pulse| network.describe(pulse)).collect::<Vec<_>>(), [
+            "n -high-> out",
+            "c -low-> out",
+            "n -high-> out",
+            "c -high-> out",
+        ]);
+    }
+
+    #[test]
+    fn test_custom_state_in_cycles() {
+        let mut registry = Registry::default();
+        registry.register_custom('!', || Box::new(Third::default()));
+        let content = "broadcaster -> t\n!t -> out";
+        let mut simulated = parse_machine_with(content, &registry).unwrap();
+        let (mut low, mut high) = (0, 0);
+        for _ in 0..10 {
+            simulated.press(|pulse| if pulse.high { high += 1 } else { low += 1 });
+        }
+
+        let mut network = parse_machine_with(content, &registry).unwrap();
+        assert_eq!(network.count_pulses(10), (low, high));
+        assert_eq!((low, high), (20, 3));
+    }
+
+    #[test]
+    fn test_prefixes_match_export() {
+        let mut registry = Registry::default();
+        registry.register_custom('!', || Box::new(Inverter));
+        for kind in ['%', '&', '*', '|', '^', '@', '#', '!'] {
+            assert_eq!(prefix(&registry.create(kind).unwrap()), kind.to_string());
+        }
+    }
+
+    #[test]
+    fn test_unknown_prefix() {
+        let error = parse_machine("broadcaster -> a\n?a -> b").err().unwrap();
+        assert_eq!(error.to_string(), "Unknown module prefix ? in front of a");
+        assert!(parse_machine_with("broadcaster -> a\n%a -> b", &Registry::empty()).is_err());
+    }
+}
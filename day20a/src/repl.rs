@@ -0,0 +1,263 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::{BufRead, Write};
+
+use crate::export::prefix;
+use crate::network::{Network, Pulse, Snapshot};
+use crate::types::{ModuleId, ModuleKind};
+
+const HELP: &str = "press [n]           press the button n times, finishing the current press first
+step [n]            deliver n single pulses
+state               print all flip-flops and conjunction memories
+break <module> [high|low]
+                    stop when the module sends a pulse of that level, high by default
+break               list breakpoints
+clear               remove all breakpoints
+rewind [press]      go back to the state after the given press, the previous one by default
+quit                leave";
+/// Checkpoints kept for rewinding, their spacing doubles whenever there would be more.
+const MAX_CHECKPOINTS: usize = 64;
+
+/// Interactive simulation of a network, one command per line.
+pub struct Session {
+    network: Network,
+    breakpoints: Vec<(ModuleId, bool)>,
+    /// State after every `interval`-th completed press, keyed by the number of presses.
+    history: BTreeMap<u64, Snapshot>,
+    interval: u64,
+}
+
+impl Session {
+    pub fn new(network: Network) -> Self {
+        let history = BTreeMap::from([(network.presses(), network.snapshot())]);
+        Self { network, breakpoints: Vec::new(), history, interval: 1 }
+    }
+
+    /// Reads commands until `quit` or the end of the input, errors of single commands are printed and skipped.
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> Result<(), Box<dyn Error>> {
+        write!(output, "> ")?;
+        output.flush()?;
+        for line in input.lines() {
+            match self.execute(&line?) {
+                Ok(Some(text)) if text.is_empty() => {}
+                Ok(Some(text)) => writeln!(output, "{}", text)?,
+                Ok(None) => return Ok(()),
+                Err(err) => writeln!(output, "error: {}", err)?,
+            }
+            write!(output, "> ")?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Runs a single command and returns what it prints, `None` once the session should end.
+    pub fn execute(&mut self, line: &str) -> Result<Option<String>, Box<dyn Error>> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let count = |idx: usize| words.get(idx).map_or(Ok(1), |count| count.parse::<u64>());
+        let text = match words[..] {
+            [] => String::new(),
+            ["press", ..] => self.press(count(1)?),
+            ["step", ..] => self.step(count(1)?),
+            ["state"] => self.state(),
+            ["break"] => self.breakpoints.iter()
+                .map(|(id, high)| format!("{} {}", self.network.name(*id), level(*high)))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            ["break", module, ..] => {
+                let id = self.network.id(module).ok_or_else(|| format!("Unknown module {}", module))?;
+                let high = match words.get(2) {
+                    None | Some(&"high") => true,
+                    Some(&"low") => false,
+                    Some(level) => return Err(format!("Unknown level {}", level).into()),
+                };
+                self.breakpoints.push((id, high));
+                format!("break when {} sends {}", module, level(high))
+            }
+            ["clear"] => {
+                self.breakpoints.clear();
+                String::new()
+            }
+            ["rewind"] => self.rewind(self.network.presses().saturating_sub(1))?,
+            ["rewind", press] => self.rewind(press.parse()?)?,
+            ["help"] => HELP.to_string(),
+            ["quit"] | ["exit"] => return Ok(None),
+            _ => return Err(format!("Unknown command {}, try help", line.trim()).into()),
+        };
+        Ok(Some(text))
+    }
+
+    fn press(&mut self, count: u64) -> String {
+        let target = self.network.presses()
+            .saturating_add(count)
+            .saturating_sub(u64::from(!self.network.is_settled()))
+            .max(self.network.presses());
+        let (mut low, mut high) = (0, 0);
+        while !(self.network.is_settled() && self.network.presses() == target) {
+            let (pulse, hit) = self.deliver();
+            if pulse.high {
+                high += 1;
+            } else {
+                low += 1;
+            }
+            if hit {
+                return format!("break at press {}: {}", self.network.presses(), self.network.describe(&pulse));
+            }
+        }
+        format!("press {}: {} low, {} high", self.network.presses(), low, high)
+    }
+
+    fn step(&mut self, count: u64) -> String {
+        let mut lines = Vec::new();
+        for _ in 0..count {
+            let (pulse, hit) = self.deliver();
+            lines.push(self.network.describe(&pulse));
+            if hit {
+                lines.push(format!("break at press {}", self.network.presses()));
+                break;
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// Delivers one pulse, pressing the button first if the network is settled, and whether it hit a breakpoint.
+    fn deliver(&mut self) -> (Pulse, bool) {
+        if self.network.is_settled() {
+            self.network.queue_button();
+        }
+        let pulse = self.network.step().expect("Could not find queued pulse");
+        if self.network.is_settled() && self.network.presses().is_multiple_of(self.interval) {
+            self.history.insert(self.network.presses(), self.network.snapshot());
+            if self.history.len() > MAX_CHECKPOINTS {
+                self.interval *= 2;
+                let interval = self.interval;
+                self.history.retain(|press, _| press.is_multiple_of(interval));
+            }
+        }
+        (pulse, self.breakpoints.contains(&(pulse.sender, pulse.high)))
+    }
+
+    /// Restores the closest checkpoint before `press` and presses the button again from there.
+    fn rewind(&mut self, press: u64) -> Result<String, Box<dyn Error>> {
+        let finished = self.network.presses() - u64::from(!self.network.is_settled());
+        if press > finished {
+            return Err(format!("Press {} has not finished yet", press).into());
+        }
+        let (_, snapshot) = self.history.range(..=press).next_back().expect("Could not find initial snapshot");
+        self.network.restore(snapshot);
+        self.history.split_off(&(press + 1));
+        while self.network.presses() < press {
+            self.network.press(|_| {});
+        }
+        Ok(format!("rewound to press {}", press))
+    }
+
+    fn state(&self) -> String {
+        let mut lines = vec![match self.network.is_settled() {
+            true => format!("press {}", self.network.presses()),
+            false => format!("press {}, pulses pending", self.network.presses()),
+        }];
+        for id in self.network.ids() {
+            let Some(module) = self.network.module(id) else {
+                continue;
+            };
+            let state = match module {
+                ModuleKind::FlipFlop(flip_flop) => String::from(if flip_flop.is_on() { "on" } else { "off" }),
+                ModuleKind::Conjunction(conjunction) => conjunction.memory()
+                    .map(|(input, high)| format!("{}={}", self.network.name(input), level(high)))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                _ => continue,
+            };
+            lines.push(format!("{}{} {}", prefix(module), self.network.name(id), state));
+        }
+        lines.join("\n")
+    }
+}
+
+fn level(high: bool) -> &'static str {
+    if high { "high" } else { "low" }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_machine;
+
+    use super::*;
+
+    fn session() -> Session {
+        let content = std::fs::read_to_string("example2.txt").unwrap();
+        Session::new(parse_machine(&content).unwrap())
+    }
+
+    fn execute(session: &mut Session, line: &str) -> String {
+        session.execute(line).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_step_and_state() {
+        let mut session = session();
+
+        assert_eq!(execute(&mut session, "step 3"), "button -low-> broadcaster\nbroadcaster -low-> a\na -high-> inv");
+        assert_eq!(execute(&mut session, "state"), [
+            "press 1, pulses pending",
+            "%a on",
+            "&inv a=high",
+            "%b off",
+            "&con a=low b=low",
+        ].join("\n"));
+        assert_eq!(execute(&mut session, "press"), "press 1: 2 low, 3 high");
+        assert_eq!(execute(&mut session, "press 3"), "press 4: 13 low, 7 high");
+    }
+
+    #[test]
+    fn test_breakpoints_and_rewind() {
+        let mut session = session();
+        execute(&mut session, "break b");
+
+        assert_eq!(execute(&mut session, "press 10"), "break at press 1: b -high-> con");
+        assert_eq!(execute(&mut session, "press 10"), "break at press 5: b -high-> con");
+        assert_eq!(execute(&mut session, "rewind"), "rewound to press 4");
+        assert_eq!(execute(&mut session, "state").lines().nth(1), Some("%a off"));
+        assert_eq!(execute(&mut session, "rewind 0"), "rewound to press 0");
+        assert_eq!(execute(&mut session, "step"), "button -low-> broadcaster");
+        assert_eq!(execute(&mut session, &format!("press {}", u64::MAX)), "break at press 1: b -high-> con");
+        assert!(session.execute("rewind 2").is_err());
+        assert!(session.execute("break nothing").is_err());
+        assert_eq!(session.execute("quit").unwrap(), None);
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut session = session();
+        execute(&mut session, "press 100000");
+
+        assert!(session.history.len() <= MAX_CHECKPOINTS);
+        assert_eq!(execute(&mut session, "rewind 12345"), "rewound to press 12345");
+        let mut expected = self::session();
+        execute(&mut expected, "press 12345");
+        assert_eq!(execute(&mut session, "state"), execute(&mut expected, "state"));
+        assert_eq!(execute(&mut session, "press"), execute(&mut expected, "press"));
+    }
+
+    #[test]
+    fn test_rewind_drops_sink_recordings() {
+        let mut session = session();
+        let output = session.network.id("output").unwrap();
+        execute(&mut session, "press 2");
+        let expected = session.network.received(output).unwrap().to_vec();
+        execute(&mut session, "press 3");
+        execute(&mut session, "rewind 1");
+        execute(&mut session, "press");
+
+        assert_eq!(session.network.received(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_run() {
+        let mut session = session();
+        let mut output = Vec::new();
+        session.run("press 2\nfly\nquit\npress\n".as_bytes(), &mut output).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "> press 2: 8 low, 6 high\n> error: Unknown command fly, try help\n> ");
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Write;
+
+use crate::network::{Network, Pulse};
+use crate::types::ModuleId;
+
+/// Watches pulses as the network delivers them.
+pub trait Observer {
+    fn observe(&mut self, network: &Network, pulse: &Pulse);
+}
+
+/// Writes every pulse in puzzle notation, one line each.
+pub struct Logger<W: Write> {
+    pub output: W,
+}
+
+/// Passes on pulses sent or received by one of `modules`, and only those of `level` if set.
+pub struct Filter<O: Observer> {
+    /// Every module is allowed if empty.
+    pub modules: Vec<String>,
+    pub level: Option<bool>,
+    pub inner: O,
+}
+
+/// Counts low and high pulses sent by each module.
+#[derive(Debug, Default)]
+pub struct PulseCounter {
+    counts: HashMap<ModuleId, (u64, u64)>,
+}
+
+/// Remembers the first press on which `module` sends a pulse of level `high`.
+#[derive(Debug)]
+pub struct FirstPress {
+    pub module: String,
+    pub high: bool,
+    pub press: Option<u64>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TraceOptions {
+    pub modules: Vec<String>,
+    pub level: Option<bool>,
+    pub presses: u64,
+}
+
+impl Network {
+    /// Like `press`, but hands every delivered pulse to `observer` together with the network.
+    pub fn press_observed(&mut self, observer: &mut impl Observer) {
+        self.queue_button();
+        while let Some(pulse) = self.step() {
+            observer.observe(self, &pulse);
+        }
+    }
+}
+
+impl<W: Write> Observer for Logger<W> {
+    fn observe(&mut self, network: &Network, pulse: &Pulse) {
+        writeln!(self.output, "{}", network.describe(pulse)).expect("Could not write pulse");
+    }
+}
+
+impl<O: Observer> Filter<O> {
+    pub fn new(inner: O) -> Self {
+        Self { modules: Vec::new(), level: None, inner }
+    }
+}
+
+impl<O: Observer> Observer for Filter<O> {
+    fn observe(&mut self, network: &Network, pulse: &Pulse) {
+        let watched = self.modules.is_empty() || self.modules.iter()
+            .any(|name| name == network.name(pulse.sender) || name == network.name(pulse.receiver));
+        if watched && self.level.is_none_or(|level| level == pulse.high) {
+            self.inner.observe(network, pulse);
+        }
+    }
+}
+
+impl PulseCounter {
+    /// Low and high pulses sent by `module` so far.
+    pub fn get(&self, module: ModuleId) -> (u64, u64) {
+        self.counts.get(&module).copied().unwrap_or_default()
+    }
+}
+
+impl Observer for PulseCounter {
+    fn observe(&mut self, _network: &Network, pulse: &Pulse) {
+        let (low, high) = self.counts.entry(pulse.sender).or_default();
+        if pulse.high {
+            *high += 1;
+        } else {
+            *low += 1;
+        }
+    }
+}
+
+impl FirstPress {
+    pub fn new(module: &str, high: bool) -> Self {
+        Self { module: module.to_string(), high, press: None }
+    }
+}
+
+impl Observer for FirstPress {
+    fn observe(&mut self, network: &Network, pulse: &Pulse) {
+        if self.press.is_none() && pulse.high == self.high && network.name(pulse.sender) == self.module {
+            self.press = Some(network.presses());
+        }
+    }
+}
+
+impl<A: Observer, B: Observer> Observer for (A, B) {
+    fn observe(&mut self, network: &Network, pulse: &Pulse) {
+        self.0.observe(network, pulse);
+        self.1.observe(network, pulse);
+    }
+}
+
+impl TraceOptions {
+    /// Reads `--trace [a,b,..]`, an optional `--level low|high` and `--presses <n>` from the command line arguments.
+    pub fn from_args(args: &[String]) -> Result<Option<Self>, Box<dyn Error>> {
+        let Some(modules) = shared::flag_argument(args, "--trace") else {
+            return Ok(None);
+        };
+        let level = match shared::flag_argument(args, "--level") {
+            None => None,
+            Some(Some("low")) => Some(false),
+            Some(Some("high")) => Some(true),
+            Some(_) => return Err("Expected low or high after --level".into()),
+        };
+        Ok(Some(Self {
+            modules: modules.map(|modules| modules.split(',').map(String::from).collect()).unwrap_or_default(),
+            level,
+            presses: shared::flag_value(args, "--presses")?.unwrap_or(1),
+        }))
+    }
+
+    /// Presses the button and writes the pulses passing the filter to `output`, prefixed by the press.
+    pub fn run(&self, network: &mut Network, output: impl Write) {
+        let mut filter = Filter {
+            modules: self.modules.clone(),
+            level: self.level,
+            inner: Logger { output },
+        };
+        for _ in 0..self.presses {
+            writeln!(filter.inner.output, "press {}", network.presses() + 1).expect("Could not write press");
+            network.press_observed(&mut filter);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_machine;
+
+    use super::*;
+
+    fn network() -> Network {
+        let content = std::fs::read_to_string("example2.txt").unwrap();
+        parse_machine(&content).unwrap()
+    }
+
+    #[test]
+    fn test_filtered_log() {
+        let mut network = network();
+        let mut filter = Filter::new(Logger { output: Vec::new() });
+        filter.modules = vec!["con".to_string()];
+        filter.level = Some(true);
+        network.press_observed(&mut filter);
+
+        assert_eq!(String::from_utf8(filter.inner.output).unwrap(), [
+            "a -high-> con",
+            "con -high-> output",
+            "b -high-> con",
+            "",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_counter_and_first_press() {
+        let mut network = network();
+        let mut observer = (PulseCounter::default(), FirstPress::new("b", true));
+        for _ in 0..4 {
+            network.press_observed(&mut observer);
+        }
+        let (counter, first) = observer;
+
+        assert_eq!(counter.get(network.id("broadcaster").unwrap()), (4, 0));
+        assert_eq!(counter.get(network.id("a").unwrap()), (4, 4));
+        assert_eq!(first.press, Some(1));
+    }
+
+    #[test]
+    fn test_options_from_args() {
+        let args: Vec<String> = ["--trace", "a,inv", "--level", "high", "--presses", "2"].map(String::from).to_vec();
+
+        assert_eq!(TraceOptions::from_args(&args).unwrap(), Some(TraceOptions {
+            modules: vec!["a".to_string(), "inv".to_string()],
+            level: Some(true),
+            presses: 2,
+        }));
+        assert!(TraceOptions::from_args(&["--trace".to_string(), "--level".to_string()]).is_err());
+    }
+}
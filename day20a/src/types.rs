@@ -1,117 +1,432 @@
 use std::collections::HashMap;
-use std::fmt::{Debug, Formatter};
+use std::fmt::Debug;
 
 pub trait Module: Debug {
-    fn update_state(&mut self, input: bool, sender: String) -> Option<bool>;
+    /// Reacts to a pulse from `sender` and returns the level sent on to every destination, if any.
+    fn update_state(&mut self, input: bool, sender: ModuleId) -> Option<bool>;
 
-    fn get_destinations(&self) -> &Vec<String>;
+    fn register_input(&mut self, input: ModuleId);
 
-    fn register_input(&mut self, input: String);
+    /// Everything the module remembers between pulses, cycle detection relies on it being complete.
+    fn state(&self) -> Vec<u64>;
+
+    /// Restores what `state` returned earlier.
+    fn restore(&mut self, state: &[u64]);
+}
+
+/// Dense id of an interned module name.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, PartialOrd, Ord)]
+pub struct ModuleId(pub u32);
+
+/// Interns module names in the order they are first seen.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Names {
+    names: Vec<String>,
+    ids: HashMap<String, ModuleId>,
 }
 
-pub struct Broadcaster {
-    destinations: Vec<String>,
+#[derive(Debug, Clone)]
+pub enum ModuleKind {
+    Broadcaster(Broadcaster),
+    FlipFlop(FlipFlop),
+    Conjunction(Conjunction),
+    Gate(Gate),
+    Delay(Delay),
+    Counter(Counter),
+    Sink(Sink),
+    /// Any other module, e.g. one registered from outside this crate, with the prefix it was registered under.
+    Custom(char, Box<dyn CustomModule>),
 }
 
+/// A module that can be cloned behind a box, implemented for every `Module` that is `Clone`.
+pub trait CustomModule: Module {
+    fn clone_box(&self) -> Box<dyn CustomModule>;
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Broadcaster;
+
+#[derive(Debug, PartialEq, Clone, Default)]
 pub struct FlipFlop {
-    input: bool,
     on: bool,
-    destinations: Vec<String>,
 }
 
+/// Remembers the last level of every input as one bit, in the order the inputs were registered.
+#[derive(Debug, PartialEq, Clone, Default)]
 pub struct Conjunction {
-    states: HashMap<String, bool>,
-    destinations: Vec<String>,
+    inputs: Vec<ModuleId>,
+    memory: Vec<u64>,
+    high: usize,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum GateOp {
+    And,
+    Or,
+    Xor,
+}
+
+/// Remembers its inputs like a conjunction and sends the result of `op` over all of them.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Gate {
+    op: GateOp,
+    memory: Conjunction,
+}
+
+/// Sends the level of the previous pulse it received, starting with low.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Delay {
+    last: bool,
+}
+
+/// Counts low pulses and sends high whenever the count wraps around `modulus`, low otherwise. Ignores high pulses.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Counter {
+    modulus: u64,
+    count: u64,
+}
+
+/// Stands in for a name that is only used as a destination, sends nothing and keeps every pulse the network
+/// delivers to it. The recordings are not part of the state, snapshots only remember how many there were.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Sink {
+    received: Vec<Received>,
+}
+
+/// A pulse delivered to a sink on the given press.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Received {
+    pub press: u64,
+    pub sender: ModuleId,
+    pub high: bool,
+}
+
+impl Names {
+    pub fn intern(&mut self, name: &str) -> ModuleId {
+        self.get(name).unwrap_or_else(|| {
+            let id = ModuleId(self.names.len() as u32);
+            self.names.push(String::from(name));
+            self.ids.insert(String::from(name), id);
+            id
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<ModuleId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: ModuleId) -> &str {
+        &self.names[id.0 as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
 }
 
-impl Broadcaster {
-    pub fn from(destinations: Vec<&str>) -> Self {
-        Self {
-            destinations: destinations.iter().map(|item| String::from(*item)).collect(),
+impl ModuleKind {
+    fn inner(&self) -> &dyn Module {
+        match self {
+            ModuleKind::Broadcaster(module) => module,
+            ModuleKind::FlipFlop(module) => module,
+            ModuleKind::Conjunction(module) => module,
+            ModuleKind::Gate(module) => module,
+            ModuleKind::Delay(module) => module,
+            ModuleKind::Counter(module) => module,
+            ModuleKind::Sink(module) => module,
+            ModuleKind::Custom(_, module) => module.as_ref(),
         }
     }
+
+    fn inner_mut(&mut self) -> &mut dyn Module {
+        match self {
+            ModuleKind::Broadcaster(module) => module,
+            ModuleKind::FlipFlop(module) => module,
+            ModuleKind::Conjunction(module) => module,
+            ModuleKind::Gate(module) => module,
+            ModuleKind::Delay(module) => module,
+            ModuleKind::Counter(module) => module,
+            ModuleKind::Sink(module) => module,
+            ModuleKind::Custom(_, module) => module.as_mut(),
+        }
+    }
+}
+
+impl Module for ModuleKind {
+    fn update_state(&mut self, input: bool, sender: ModuleId) -> Option<bool> {
+        self.inner_mut().update_state(input, sender)
+    }
+
+    fn register_input(&mut self, input: ModuleId) {
+        self.inner_mut().register_input(input)
+    }
+
+    fn state(&self) -> Vec<u64> {
+        self.inner().state()
+    }
+
+    fn restore(&mut self, state: &[u64]) {
+        self.inner_mut().restore(state)
+    }
+}
+
+impl<T: Module + Clone + 'static> CustomModule for T {
+    fn clone_box(&self) -> Box<dyn CustomModule> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn CustomModule> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl Module for Broadcaster {
+    fn update_state(&mut self, input: bool, _sender: ModuleId) -> Option<bool> {
+        Some(input)
+    }
+
+    fn register_input(&mut self, _input: ModuleId) {}
+
+    fn state(&self) -> Vec<u64> {
+        Vec::new()
+    }
+
+    fn restore(&mut self, _state: &[u64]) {}
 }
 
 impl FlipFlop {
-    pub fn from(destinations: Vec<&str>) -> Self {
-        Self {
-            input: false,
-            on: false,
-            destinations: destinations.iter().map(|item| String::from(*item)).collect(),
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+}
+
+impl Module for FlipFlop {
+    fn update_state(&mut self, input: bool, _sender: ModuleId) -> Option<bool> {
+        if input {
+            return None;
         }
+        self.on = !self.on;
+        Some(self.on)
+    }
+
+    fn register_input(&mut self, _input: ModuleId) {}
+
+    fn state(&self) -> Vec<u64> {
+        vec![self.on as u64]
+    }
+
+    fn restore(&mut self, state: &[u64]) {
+        self.on = state[0] != 0;
     }
 }
 
 impl Conjunction {
-    pub fn from(destinations: Vec<&str>) -> Self {
-        Self {
-            states: Default::default(),
-            destinations: destinations.iter().map(|item| String::from(*item)).collect(),
+    /// Every registered input with the level it sent last.
+    pub fn memory(&self) -> impl Iterator<Item=(ModuleId, bool)> + '_ {
+        self.inputs.iter()
+            .enumerate()
+            .map(|(idx, input)| (*input, self.memory[idx / 64] & (1 << (idx % 64)) != 0))
+    }
+}
+
+impl Module for Conjunction {
+    fn update_state(&mut self, input: bool, sender: ModuleId) -> Option<bool> {
+        let idx = self.inputs.iter()
+            .position(|known| *known == sender)
+            .expect("Conjunction received pulse from unregistered input");
+        let (word, bit) = (idx / 64, 1 << (idx % 64));
+        if (self.memory[word] & bit != 0) != input {
+            self.memory[word] ^= bit;
+            if input {
+                self.high += 1;
+            } else {
+                self.high -= 1;
+            }
         }
+        Some(self.high != self.inputs.len())
+    }
+
+    fn register_input(&mut self, input: ModuleId) {
+        if self.inputs.contains(&input) {
+            return;
+        }
+        self.inputs.push(input);
+        if self.memory.len() * 64 < self.inputs.len() {
+            self.memory.push(0);
+        }
+    }
+
+    fn state(&self) -> Vec<u64> {
+        self.memory.clone()
+    }
+
+    fn restore(&mut self, state: &[u64]) {
+        self.memory = state.to_vec();
+        self.high = self.memory.iter().map(|word| word.count_ones() as usize).sum();
     }
 }
 
-impl Debug for Broadcaster {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Broadcaster -> {:?}", self.destinations)
+impl Gate {
+    pub fn new(op: GateOp) -> Self {
+        Self { op, memory: Conjunction::default() }
+    }
+
+    pub fn op(&self) -> GateOp {
+        self.op
     }
 }
 
-impl Module for Broadcaster {
-    fn update_state(&mut self, _input: bool, _sender: String) -> Option<bool> {
-        Some(false)
+impl Module for Gate {
+    fn update_state(&mut self, input: bool, sender: ModuleId) -> Option<bool> {
+        self.memory.update_state(input, sender);
+        let high = self.memory.high;
+        Some(match self.op {
+            GateOp::And => high == self.memory.inputs.len(),
+            GateOp::Or => high > 0,
+            GateOp::Xor => high % 2 == 1,
+        })
+    }
+
+    fn register_input(&mut self, input: ModuleId) {
+        self.memory.register_input(input);
+    }
+
+    fn state(&self) -> Vec<u64> {
+        self.memory.state()
+    }
+
+    fn restore(&mut self, state: &[u64]) {
+        self.memory.restore(state);
+    }
+}
+
+impl Module for Delay {
+    fn update_state(&mut self, input: bool, _sender: ModuleId) -> Option<bool> {
+        Some(std::mem::replace(&mut self.last, input))
     }
 
-    fn get_destinations(&self) -> &Vec<String> {
-        &self.destinations
+    fn register_input(&mut self, _input: ModuleId) {}
+
+    fn state(&self) -> Vec<u64> {
+        vec![self.last as u64]
     }
 
-    fn register_input(&mut self, _input: String) {}
+    fn restore(&mut self, state: &[u64]) {
+        self.last = state[0] != 0;
+    }
 }
 
-impl Debug for FlipFlop {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "FlipFlop input: {}, on: {} -> {:?}", self.input, self.on, self.destinations)
+impl Counter {
+    pub fn new(modulus: u64) -> Self {
+        Self { modulus, count: 0 }
     }
 }
 
-impl Module for FlipFlop {
-    fn update_state(&mut self, input: bool, _sender: String) -> Option<bool> {
-        if !input {
-            self.on = !self.on;
-        }
-        self.input = input;
-        if !input {
-            Some(self.on)
-        } else {
-            None
+impl Module for Counter {
+    fn update_state(&mut self, input: bool, _sender: ModuleId) -> Option<bool> {
+        if input {
+            return None;
         }
+        self.count = (self.count + 1) % self.modulus;
+        Some(self.count == 0)
     }
 
-    fn get_destinations(&self) -> &Vec<String> {
-        &self.destinations
+    fn register_input(&mut self, _input: ModuleId) {}
+
+    fn state(&self) -> Vec<u64> {
+        vec![self.count]
     }
 
-    fn register_input(&mut self, _input: String) {}
+    fn restore(&mut self, state: &[u64]) {
+        self.count = state[0];
+    }
 }
 
-impl Debug for Conjunction {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Conjunction states: {:?}", self.states)
+impl Sink {
+    pub fn record(&mut self, received: Received) {
+        self.received.push(received);
+    }
+
+    pub fn received(&self) -> &[Received] {
+        &self.received
+    }
+
+    /// Forgets everything but the first `len` recordings.
+    pub fn truncate(&mut self, len: usize) {
+        self.received.truncate(len);
     }
 }
 
-impl Module for Conjunction {
-    fn update_state(&mut self, input: bool, sender: String) -> Option<bool> {
-        *self.states.get_mut(&sender).unwrap() = input;
-        Some(!self.states.iter().all(|(_, state)| *state))
+/// Pulses are recorded by the network through `record`, which also knows the press.
+impl Module for Sink {
+    fn update_state(&mut self, _input: bool, _sender: ModuleId) -> Option<bool> {
+        None
     }
 
-    fn get_destinations(&self) -> &Vec<String> {
-        &self.destinations
+    fn register_input(&mut self, _input: ModuleId) {}
+
+    /// Recordings are restored by truncating them, they never repeat.
+    fn state(&self) -> Vec<u64> {
+        Vec::new()
     }
 
-    fn register_input(&mut self, input: String) {
-        self.states.insert(input, false);
+    fn restore(&mut self, _state: &[u64]) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flip_flop() {
+        let mut module = FlipFlop::default();
+
+        assert_eq!(module.update_state(true, ModuleId(0)), None);
+        assert_eq!(module.update_state(false, ModuleId(0)), Some(true));
+        assert_eq!(module.update_state(false, ModuleId(0)), Some(false));
+    }
+
+    #[test]
+    fn test_conjunction_with_many_inputs() {
+        let mut module = Conjunction::default();
+        for idx in 0..70 {
+            module.register_input(ModuleId(idx));
+        }
+
+        for idx in 0..69 {
+            assert_eq!(module.update_state(true, ModuleId(idx)), Some(true));
+        }
+        assert_eq!(module.update_state(true, ModuleId(69)), Some(false));
+        assert_eq!(module.update_state(false, ModuleId(3)), Some(true));
+    }
+
+    #[test]
+    fn test_gates() {
+        let mut gates = [GateOp::And, GateOp::Or, GateOp::Xor].map(Gate::new);
+        for gate in &mut gates {
+            gate.register_input(ModuleId(0));
+            gate.register_input(ModuleId(1));
+        }
+        let outputs = |gates: &mut [Gate; 3], input, sender| gates.each_mut().map(|gate| gate.update_state(input, sender));
+
+        assert_eq!(outputs(&mut gates, true, ModuleId(0)), [Some(false), Some(true), Some(true)]);
+        assert_eq!(outputs(&mut gates, true, ModuleId(1)), [Some(true), Some(true), Some(false)]);
+        assert_eq!(outputs(&mut gates, false, ModuleId(0)), [Some(false), Some(true), Some(true)]);
+    }
+
+    #[test]
+    fn test_delay_and_counter() {
+        let mut delay = Delay::default();
+        let mut counter = Counter::new(3);
+
+        assert_eq!(delay.update_state(true, ModuleId(0)), Some(false));
+        assert_eq!(delay.update_state(false, ModuleId(0)), Some(true));
+        assert_eq!([false, true, false, false].map(|input| counter.update_state(input, ModuleId(0))), [Some(false), None, Some(false), Some(true)]);
     }
 }
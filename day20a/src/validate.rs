@@ -0,0 +1,80 @@
+use std::fmt::{Display, Formatter};
+
+use crate::network::Network;
+use crate::types::ModuleKind;
+
+#[derive(Debug, PartialEq)]
+pub enum Finding {
+    /// A module with a line of its own that no pulse from the broadcaster can ever reach.
+    UnreachableModule { module: String },
+    /// A destination without a line of its own, which only records what it receives.
+    DanglingDestination { module: String, destination: String },
+}
+
+/// Reports unreachable modules and dangling destinations, neither of them stops the network from working.
+pub fn validate(network: &Network) -> Vec<Finding> {
+    let broadcaster = network.id("broadcaster").expect("Could not find broadcaster");
+    let mut reachable = vec![false; network.ids().count()];
+    reachable[broadcaster.0 as usize] = true;
+    let mut stack = vec![broadcaster];
+    while let Some(id) = stack.pop() {
+        for destination in network.destinations(id) {
+            if !reachable[destination.0 as usize] {
+                reachable[destination.0 as usize] = true;
+                stack.push(*destination);
+            }
+        }
+    }
+
+    let mut findings: Vec<Finding> = network.ids()
+        .filter(|id| !reachable[id.0 as usize])
+        .filter(|id| !matches!(network.module(*id), None | Some(ModuleKind::Sink(_))))
+        .map(|id| Finding::UnreachableModule { module: network.name(id).to_string() })
+        .collect();
+    for id in network.ids() {
+        findings.extend(network.destinations(id).iter()
+            .filter(|destination| network.received(**destination).is_some())
+            .map(|destination| Finding::DanglingDestination {
+                module: network.name(id).to_string(),
+                destination: network.name(*destination).to_string(),
+            }));
+    }
+    findings
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Finding::UnreachableModule { module } => write!(f, "Module '{}' is never reached from 'broadcaster'", module),
+            Finding::DanglingDestination { module, destination } => write!(f, "Module '{}' sends to undefined module '{}'", module, destination),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_machine;
+
+    use super::*;
+
+    #[test]
+    fn test_example1_is_clean() {
+        let content = std::fs::read_to_string("example1.txt").unwrap();
+
+        assert_eq!(validate(&parse_machine(&content).unwrap()), vec![]);
+    }
+
+    #[test]
+    fn test_reports_all_findings() {
+        let network = parse_machine("broadcaster -> a\n%a -> con\n&con -> out\n%lost -> con, gone\n&loop -> loop").unwrap();
+        let findings = validate(&network);
+
+        assert_eq!(findings, vec![
+            Finding::UnreachableModule { module: "lost".to_string() },
+            Finding::UnreachableModule { module: "loop".to_string() },
+            Finding::DanglingDestination { module: "con".to_string(), destination: "out".to_string() },
+            Finding::DanglingDestination { module: "lost".to_string(), destination: "gone".to_string() },
+        ]);
+        assert_eq!(findings[3].to_string(), "Module 'lost' sends to undefined module 'gone'");
+    }
+}
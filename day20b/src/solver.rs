@@ -0,0 +1,178 @@
+use std::error::Error;
+
+use day20a::network::Network;
+use day20a::types::ModuleKind;
+
+/// Presses simulated before giving up on both the periods and the sink itself.
+const MAX_PRESSES: u64 = 10_000_000;
+/// Hits needed per input before its period counts as confirmed.
+const HITS: usize = 3;
+
+/// Finds the first press on which `sink` receives a low pulse.
+///
+/// If the sink is fed by a single conjunction, every input of that conjunction is assumed to send high periodically,
+/// so the periods are measured by simulation and combined. Otherwise the button is pressed until the sink is reached.
+pub fn presses_until_low(network: &mut Network, sink: &str) -> Result<u64, Box<dyn Error>> {
+    let sink = network.id(sink)
+        .filter(|sink| network.received(*sink).is_some())
+        .ok_or("No module sends pulses to the sink")?;
+    let feeders = network.inputs(sink);
+    let mut watched = match feeders[..] {
+        [feeder] if matches!(network.module(feeder), Some(ModuleKind::Conjunction(_))) => Some((feeder, network.inputs(feeder))),
+        _ => None,
+    };
+
+    let mut hits: Vec<Vec<u64>> = watched.iter().flat_map(|(_, inputs)| inputs.iter().map(|_| Vec::new())).collect();
+    while network.presses() < MAX_PRESSES {
+        let press = network.presses() + 1;
+        let mut reached = false;
+        network.press(|pulse| {
+            reached |= pulse.receiver == sink && !pulse.high;
+            if let Some((feeder, inputs)) = &watched {
+                if pulse.receiver == *feeder && pulse.high {
+                    let idx = inputs.iter().position(|input| *input == pulse.sender).expect("Could not find input");
+                    if hits[idx].last() != Some(&press) {
+                        hits[idx].push(press);
+                    }
+                }
+            }
+        });
+        if reached {
+            return Ok(press);
+        }
+        network.clear_received(sink);
+        if !hits.is_empty() && hits.iter().all(|hits| hits.len() >= HITS) {
+            if let Some(result) = combine_periods(&hits) {
+                return Ok(result);
+            }
+            watched = None;
+            hits.clear();
+        }
+    }
+    Err(format!("Sink was not reached within {} presses", MAX_PRESSES).into())
+}
+
+/// Combines the first hit and period of every input, `None` if an input is not periodic or they never align.
+fn combine_periods(hits: &[Vec<u64>]) -> Option<u64> {
+    let mut combined = (0, 1);
+    let mut first = 0;
+    for hits in hits {
+        let period = hits[1] - hits[0];
+        if hits.windows(2).any(|pair| pair[1] - pair[0] != period) {
+            return None;
+        }
+        combined = crt(combined, (hits[0] % period, period))?;
+        first = first.max(hits[0]);
+    }
+    let (remainder, modulus) = combined;
+    let steps = first.saturating_sub(remainder).div_ceil(modulus);
+    remainder.checked_add(steps.checked_mul(modulus)?)
+}
+
+/// Solves `x ≡ a.0 (mod a.1)` and `x ≡ b.0 (mod b.1)` for moduli that need not be coprime.
+fn crt(a: (u64, u64), b: (u64, u64)) -> Option<(u64, u64)> {
+    let (gcd, x, _) = extended_gcd(a.1 as i128, b.1 as i128);
+    let difference = b.0 as i128 - a.0 as i128;
+    if difference % gcd != 0 {
+        return None;
+    }
+    let modulus = a.1 as i128 / gcd * b.1 as i128;
+    let step = (difference / gcd * x).rem_euclid(b.1 as i128 / gcd);
+    let remainder = (a.0 as i128 + a.1 as i128 * step).rem_euclid(modulus);
+    Some((u64::try_from(remainder).ok()?, u64::try_from(modulus).ok()?))
+}
+
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        return (a, 1, 0);
+    }
+    let (gcd, x, y) = extended_gcd(b, a % b);
+    (gcd, y, x - a / b * y)
+}
+
+#[cfg(test)]
+mod tests {
+    use day20a::parser::{parse_machine, parse_machine_with};
+    use day20a::registry::Registry;
+    use day20a::types::{Module, ModuleId};
+
+    use super::*;
+
+    /// Sends high on the first of its two pulses per press if the press is listed, low otherwise.
+    #[derive(Debug, Clone)]
+    struct Spike {
+        presses: Vec<u64>,
+        received: u64,
+    }
+
+    impl Module for Spike {
+        fn update_state(&mut self, _input: bool, _sender: ModuleId) -> Option<bool> {
+            self.received += 1;
+            Some(self.received % 2 == 1 && self.presses.contains(&self.received.div_ceil(2)))
+        }
+
+        fn register_input(&mut self, _input: ModuleId) {}
+
+        fn state(&self) -> Vec<u64> {
+            vec![self.received]
+        }
+
+        fn restore(&mut self, state: &[u64]) {
+            self.received = state[0];
+        }
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt((0, 1), (3, 4)), Some((3, 4)));
+        assert_eq!(crt((3, 4), (2, 5)), Some((7, 20)));
+        assert_eq!(crt((1, 4), (3, 6)), Some((9, 12)));
+        assert_eq!(crt((0, 4), (1, 6)), None);
+    }
+
+    #[test]
+    fn test_combine_periods() {
+        assert_eq!(combine_periods(&[vec![3, 7, 11], vec![5, 10, 15]]), Some(15));
+        assert_eq!(combine_periods(&[vec![4001, 8002, 12003], vec![3907, 7814, 11721]]), Some(4001 * 3907));
+        assert_eq!(combine_periods(&[vec![3, 7, 12]]), None);
+    }
+
+    #[test]
+    fn test_falls_back_to_simulation() {
+        let mut registry = Registry::default();
+        registry.register_custom('<', || Box::new(Spike { presses: vec![1, 2, 4, 10], received: 0 }))
+            .register_custom('>', || Box::new(Spike { presses: vec![3, 5, 6, 10], received: 0 }));
+        let content = "broadcaster -> s, t, s, t\n<s -> f\n>t -> f\n&f -> rx";
+        let mut network = parse_machine_with(content, &registry).unwrap();
+
+        assert_eq!(presses_until_low(&mut network, "rx").unwrap(), 10);
+        let rx = network.id("rx").unwrap();
+        assert!(network.received(rx).unwrap().iter().all(|received| received.press == 10));
+    }
+
+    #[test]
+    fn test_counters_match_simulation() {
+        let content = [
+            "broadcaster -> a1, b1",
+            "%a1 -> a2, ca",
+            "%a2 -> a3, ca",
+            "%a3 -> ca",
+            "&ca -> ia",
+            "&ia -> f",
+            "%b1 -> b2, cb",
+            "%b2 -> cb",
+            "&cb -> ib",
+            "&ib -> f",
+            "&f -> rx",
+        ].join("\n");
+
+        let mut simulated = parse_machine(&content).unwrap();
+        let rx = simulated.id("rx").unwrap();
+        while simulated.received(rx).unwrap().iter().all(|received| received.high) {
+            simulated.press(|_| {});
+        }
+
+        let mut network = parse_machine(&content).unwrap();
+        assert_eq!(presses_until_low(&mut network, "rx").unwrap(), simulated.presses());
+    }
+}
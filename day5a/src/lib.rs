@@ -1,95 +1,108 @@
 use std::cmp::{max, min};
-use std::error::Error;
-use std::fs;
 
+#[cfg(feature = "parallel")]
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 
+pub use crate::parser::{parse, Almanac, AlmanacError};
+pub use crate::trace::{trace, TraceNode, TraceOptions};
+
+mod parser;
+mod trace;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Input {
     source: u64,
     destination: u64,
     range: u64,
 }
 
-pub fn parse_file(filename: &str) -> Result<(Vec<u64>, Vec<Vec<Input>>), Box<dyn Error>> {
-    let content = fs::read_to_string(filename)?;
-
-    let blocks: Vec<String> = content.split("\n\n").map(String::from).collect();
-
-    let seeds: Vec<u64> = blocks
-        .get(0)
-        .expect("Could not find blocks")
-        .split_whitespace()
-        .skip(1)
-        .map(|val| val.parse::<u64>().expect("Could not parse seed"))
-        .collect();
-
-    let maps = parse_maps(&blocks);
-    Ok((seeds, maps))
+#[derive(Debug, PartialEq)]
+pub struct Map<'a> {
+    pub source: &'a str,
+    pub destination: &'a str,
+    pub inputs: Vec<Input>,
 }
 
-fn parse_maps(blocks: &Vec<String>) -> Vec<Vec<Input>> {
-    let mut maps: Vec<Vec<Input>> = Vec::new();
-    for block in blocks
-        .iter()
-        .skip(1) {
-        let mut map = Vec::new();
-        for line in block.split("\n")
-            .skip(1) {
-            let items: Vec<u64> = line.split_whitespace()
-                .map(|val| val.parse::<u64>().expect("Could not "))
-                .collect();
-            map.push(Input {
-                source: *items.get(1).unwrap(),
-                destination: *items.get(0).unwrap(),
-                range: *items.get(2).unwrap(),
-            })
-        }
-        maps.push(map);
-    }
+pub fn find_min_solution(seeds: &[(u64, u64)], maps: &[Map]) -> u64 {
+    #[cfg(feature = "parallel")]
+    return find_min_solution_parallel(seeds, maps);
+    #[cfg(not(feature = "parallel"))]
+    find_min_solution_sequential(seeds, maps)
+}
 
-    maps
+pub fn find_min_solution_sequential(seeds: &[(u64, u64)], maps: &[Map]) -> u64 {
+    seeds.iter()
+        .flat_map(|val| find_location(*val, maps))
+        .min()
+        .expect("Could not find solution")
 }
 
-pub fn find_min_solution(seeds: &Vec<(u64, u64)>, maps: &Vec<Vec<Input>>) -> u64 {
+#[cfg(feature = "parallel")]
+pub fn find_min_solution_parallel(seeds: &[(u64, u64)], maps: &[Map]) -> u64 {
     seeds.par_iter()
         .flat_map(|val| find_location(*val, maps))
         .min()
         .expect("Could not find solution")
 }
 
-fn find_location(seed: (u64, u64), maps: &Vec<Vec<Input>>) -> Vec<u64> {
-    let mut destination: Vec<(u64, u64)> = Vec::new();
-    destination.push(seed);
+/// Times the sequential solver against the parallel one, if the `parallel` feature is enabled.
+pub fn compare_modes(seeds: &[(u64, u64)], maps: &[Map], runs: u32) {
+    let sequential = || find_min_solution_sequential(seeds, maps);
+    #[cfg(feature = "parallel")]
+    let parallel = || find_min_solution_parallel(seeds, maps);
+
+    shared::compare_modes(runs, &[
+        ("sequential", &sequential),
+        #[cfg(feature = "parallel")]
+        ("parallel", &parallel),
+    ]);
+}
+
+fn find_location(seed: (u64, u64), maps: &[Map]) -> Vec<u64> {
+    let mut destination: Vec<(u64, u64)> = vec![seed];
     for map in maps {
-        destination = find_destination_range(destination, map);
+        destination = find_destination_range(destination, &map.inputs);
     }
     destination.iter()
         .map(|val| val.0)
         .collect()
 }
 
-fn find_destination_range(sources: Vec<(u64, u64)>, map: &Vec<Input>) -> Vec<(u64, u64)> {
+fn find_destination_range(sources: Vec<(u64, u64)>, map: &[Input]) -> Vec<(u64, u64)> {
     let mut destinations: Vec<(u64, u64)> = Vec::new();
     for source in sources {
-        let mut destination_ranges = map.iter()
-            .filter_map(|input| input.find_destination_range(source))
-            .collect::<Vec<(u64, u64)>>();
-        if destination_ranges.is_empty() {
-            destinations.push(source);
-        }
-        destinations.append(&mut destination_ranges);
+        destinations.extend(map_range(source, map)
+            .into_iter()
+            .map(|(_, range)| range));
     }
     destinations
 }
 
+/// Maps a single source range and reports the index of the rule that produced each destination range,
+/// `None` if no rule matched and the range is passed through unchanged. An empty range maps to nothing.
+fn map_range(source: (u64, u64), map: &[Input]) -> Vec<(Option<usize>, (u64, u64))> {
+    if source.1 == 0 {
+        return Vec::new();
+    }
+    let destination_ranges = map.iter()
+        .enumerate()
+        .filter_map(|(idx, input)| input.find_destination_range(source).map(|range| (Some(idx), range)))
+        .collect::<Vec<_>>();
+    if destination_ranges.is_empty() {
+        return vec![(None, source)];
+    }
+    destination_ranges
+}
+
 impl Input {
+    /// Compares inclusive upper bounds, which cannot overflow as long as `parse` accepted the almanac.
     fn find_destination_range(&self, source: (u64, u64)) -> Option<(u64, u64)> {
-        let source_upper = source.0 + source.1;
-        let self_upper = self.source + self.range;
-        if source.0 <= self_upper && self.source <= source_upper {
+        let source_last = source.0 + (source.1 - 1);
+        let self_last = self.source + (self.range - 1);
+        if source.0 <= self_last && self.source <= source_last {
             let min_source = max(source.0, self.source);
-            let upper_bound = min(source_upper, self_upper);
-            let target_range = upper_bound - min_source;
+            let last = min(source_last, self_last);
+            let target_range = last - min_source + 1;
             let destination = self.destination + (min_source - self.source);
             return Some((destination, target_range));
         }
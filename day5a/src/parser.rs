@@ -0,0 +1,170 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use crate::{Input, Map};
+
+#[derive(Debug, PartialEq)]
+pub struct Almanac<'a> {
+    pub seeds: Vec<u64>,
+    pub maps: Vec<Map<'a>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AlmanacError {
+    Parse { line: usize, message: String },
+    /// A range whose last value does not fit into a `u64`.
+    Overflow { line: usize, start: u64, length: u64 },
+    EmptyRange { line: usize },
+}
+
+/// Parses an almanac without copying it, every map borrows its category names from `content`.
+pub fn parse(content: &str) -> Result<Almanac<'_>, AlmanacError> {
+    let mut lines = content.lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    let (line, seeds) = lines.next()
+        .ok_or_else(|| AlmanacError::parse(1, "Could not find seeds"))?;
+    let seeds = seeds.strip_prefix("seeds:")
+        .ok_or_else(|| AlmanacError::parse(line, "Expected seeds"))?;
+    let seeds = parse_numbers(line, seeds)?;
+
+    let mut maps: Vec<Map> = Vec::new();
+    for (line, content) in lines {
+        if let Some(header) = content.strip_suffix(" map:") {
+            let (source, destination) = header.split_once("-to-")
+                .ok_or_else(|| AlmanacError::parse(line, "Expected <source>-to-<destination> map:"))?;
+            maps.push(Map {
+                source,
+                destination,
+                inputs: Vec::new(),
+            });
+            continue;
+        }
+        let map = maps.last_mut()
+            .ok_or_else(|| AlmanacError::parse(line, "Found rule outside of a map"))?;
+        map.inputs.push(parse_input(line, content)?);
+    }
+
+    Ok(Almanac { seeds, maps })
+}
+
+fn parse_input(line: usize, content: &str) -> Result<Input, AlmanacError> {
+    let [destination, source, range] = parse_numbers(line, content)?[..] else {
+        return Err(AlmanacError::parse(line, "Expected <destination> <source> <range>"));
+    };
+    check_range(line, source, range)?;
+    check_range(line, destination, range)?;
+    Ok(Input {
+        source,
+        destination,
+        range,
+    })
+}
+
+fn parse_numbers(line: usize, content: &str) -> Result<Vec<u64>, AlmanacError> {
+    content.split_whitespace()
+        .map(|val| val.parse::<u64>()
+            .map_err(|err| AlmanacError::parse(line, &format!("Could not parse {}: {}", val, err))))
+        .collect()
+}
+
+fn check_range(line: usize, start: u64, length: u64) -> Result<(), AlmanacError> {
+    if length == 0 {
+        return Err(AlmanacError::EmptyRange { line });
+    }
+    match start.checked_add(length - 1) {
+        Some(_) => Ok(()),
+        None => Err(AlmanacError::Overflow { line, start, length }),
+    }
+}
+
+impl Almanac<'_> {
+    /// Every seed as a range of length one.
+    pub fn seeds(&self) -> Vec<(u64, u64)> {
+        self.seeds.iter()
+            .map(|seed| (*seed, 1))
+            .collect()
+    }
+
+    /// Seeds read as pairs of start and length.
+    pub fn seed_ranges(&self) -> Result<Vec<(u64, u64)>, AlmanacError> {
+        self.seeds.chunks(2)
+            .map(|chunk| match chunk {
+                [start, length] => check_range(1, *start, *length).map(|_| (*start, *length)),
+                _ => Err(AlmanacError::parse(1, "Expected pairs of seeds")),
+            })
+            .collect()
+    }
+}
+
+impl AlmanacError {
+    fn parse(line: usize, message: &str) -> Self {
+        AlmanacError::Parse {
+            line,
+            message: String::from(message),
+        }
+    }
+}
+
+impl Display for AlmanacError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlmanacError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            AlmanacError::Overflow { line, start, length } => write!(f, "line {}: range {} (+{}) exceeds u64::MAX", line, start, length),
+            AlmanacError::EmptyRange { line } => write!(f, "line {}: found empty range", line),
+        }
+    }
+}
+
+impl Error for AlmanacError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_example() {
+        let content = std::fs::read_to_string("example.txt").unwrap();
+        let almanac = parse(&content).unwrap();
+
+        assert_eq!(almanac.seeds, vec![79, 14, 55, 13]);
+        assert_eq!(almanac.maps.len(), 7);
+        assert_eq!((almanac.maps[0].source, almanac.maps[0].destination), ("seed", "soil"));
+        assert_eq!(almanac.maps[0].inputs[1], Input { source: 50, destination: 52, range: 48 });
+        assert_eq!(almanac.seed_ranges(), Ok(vec![(79, 14), (55, 13)]));
+    }
+
+    #[test]
+    fn test_parse_max_values() {
+        let max = u64::MAX;
+        let content = format!("seeds: {} 1\n\nseed-to-soil map:\n{} {} 10\n", max - 4, max - 9, max - 9);
+        let almanac = parse(&content).unwrap();
+
+        assert_eq!(almanac.seed_ranges(), Ok(vec![(max - 4, 1)]));
+        assert_eq!(crate::find_min_solution(&almanac.seed_ranges().unwrap(), &almanac.maps), max - 4);
+    }
+
+    #[test]
+    fn test_parse_overflow() {
+        let content = format!("seeds: 1\n\nseed-to-soil map:\n0 {} 2\n", u64::MAX);
+
+        assert_eq!(parse(&content), Err(AlmanacError::Overflow { line: 4, start: u64::MAX, length: 2 }));
+    }
+
+    #[test]
+    fn test_parse_empty_range() {
+        let almanac = parse("seeds: 3 0").unwrap();
+
+        assert_eq!(almanac.seed_ranges(), Err(AlmanacError::EmptyRange { line: 1 }));
+        assert_eq!(parse("seeds: 1\n\na-to-b map:\n1 2 0"), Err(AlmanacError::EmptyRange { line: 4 }));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse("seeds: 1 x"), Err(AlmanacError::parse(1, "Could not parse x: invalid digit found in string")));
+        assert_eq!(parse("seeds: 1\n\n1 2 3"), Err(AlmanacError::parse(3, "Found rule outside of a map")));
+        assert_eq!(parse("seeds: 1\n\na-to-b map:\n1 2"), Err(AlmanacError::parse(4, "Expected <destination> <source> <range>")));
+    }
+}
@@ -0,0 +1,174 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use serde::Serialize;
+
+use crate::{map_range, Input, Map};
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TraceNode {
+    pub category: String,
+    pub start: u64,
+    pub length: u64,
+    /// Rule of the previous map that produced this range, `None` if the range was passed through unmapped.
+    pub rule: Option<TraceRule>,
+    pub children: Vec<TraceNode>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TraceRule {
+    pub index: usize,
+    pub destination: u64,
+    pub source: u64,
+    pub range: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TraceOptions {
+    pub seed: u64,
+    pub json: bool,
+}
+
+/// Follows a seed range through every map and records each split produced along the way.
+pub fn trace(seed: (u64, u64), maps: &[Map]) -> TraceNode {
+    let category = maps.first()
+        .map(|map| map.source)
+        .unwrap_or("seed");
+    trace_range(category, seed, None, maps)
+}
+
+fn trace_range(category: &str, range: (u64, u64), rule: Option<TraceRule>, maps: &[Map]) -> TraceNode {
+    let children = match maps.split_first() {
+        Some((map, rest)) => map_range(range, &map.inputs)
+            .into_iter()
+            .map(|(idx, destination)| {
+                let rule = idx.map(|idx| TraceRule::from(idx, &map.inputs[idx]));
+                trace_range(map.destination, destination, rule, rest)
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    TraceNode {
+        category: String::from(category),
+        start: range.0,
+        length: range.1,
+        rule,
+        children,
+    }
+}
+
+impl TraceRule {
+    fn from(index: usize, input: &Input) -> Self {
+        Self {
+            index,
+            destination: input.destination,
+            source: input.source,
+            range: input.range,
+        }
+    }
+}
+
+impl TraceNode {
+    pub fn to_json(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    fn write_indented(&self, f: &mut Formatter<'_>, depth: usize) -> std::fmt::Result {
+        write!(f, "{:indent$}{} {} (+{})", "", self.category, self.start, self.length, indent = depth * 2)?;
+        match &self.rule {
+            Some(rule) => writeln!(f, " via rule #{} [{} {} {}]", rule.index, rule.destination, rule.source, rule.range)?,
+            None if depth > 0 => writeln!(f, " unmapped")?,
+            None => writeln!(f)?,
+        }
+        self.children.iter()
+            .try_for_each(|child| child.write_indented(f, depth + 1))
+    }
+}
+
+impl Display for TraceNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.write_indented(f, 0)
+    }
+}
+
+impl TraceOptions {
+    /// Reads `--trace <seed>` and an optional `--json` from the command line arguments.
+    pub fn from_args(args: &[String]) -> Result<Option<Self>, Box<dyn Error>> {
+        let Some(seed) = shared::flag_value(args, "--trace")? else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            seed,
+            json: shared::has_flag(args, "--json"),
+        }))
+    }
+
+    /// Traces the seed range containing the requested seed, or just the seed itself if no range contains it.
+    pub fn render(&self, seeds: &[(u64, u64)], maps: &[Map]) -> Result<String, Box<dyn Error>> {
+        let range = seeds.iter()
+            .find(|(start, length)| length.checked_sub(1)
+                .and_then(|rest| start.checked_add(rest))
+                .is_some_and(|last| (*start..=last).contains(&self.seed)))
+            .copied()
+            .unwrap_or((self.seed, 1));
+        let node = trace(range, maps);
+        if self.json {
+            node.to_json()
+        } else {
+            Ok(node.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_example_seed() {
+        let content = std::fs::read_to_string("example.txt").unwrap();
+        let almanac = crate::parse(&content).unwrap();
+        let node = trace((79, 1), &almanac.maps);
+
+        let mut categories = Vec::new();
+        let mut current = &node;
+        loop {
+            categories.push((current.category.as_str(), current.start));
+            match current.children.first() {
+                Some(child) => current = child,
+                None => break,
+            }
+        }
+        assert_eq!(categories, vec![
+            ("seed", 79),
+            ("soil", 81),
+            ("fertilizer", 81),
+            ("water", 81),
+            ("light", 74),
+            ("temperature", 78),
+            ("humidity", 78),
+            ("location", 82),
+        ]);
+        assert_eq!(node.children[0].rule, Some(TraceRule {
+            index: 1,
+            destination: 52,
+            source: 50,
+            range: 48,
+        }));
+    }
+
+    #[test]
+    fn test_render_edge_ranges() {
+        let max = u64::MAX;
+        let options = TraceOptions { seed: max - 1, json: false };
+
+        assert_eq!(options.render(&[(0, 0), (max - 4, 5)], &[]).unwrap(), format!("seed {} (+5)\n", max - 4));
+        assert_eq!(trace((7, 0), &crate::parse("seeds: 1\n\nseed-to-soil map:\n0 5 3").unwrap().maps).children, vec![]);
+    }
+
+    #[test]
+    fn test_options_from_args() {
+        let args = vec![String::from("--trace"), String::from("79"), String::from("--json")];
+        assert_eq!(TraceOptions::from_args(&args).unwrap(), Some(TraceOptions { seed: 79, json: true }));
+    }
+}
@@ -1,25 +1,33 @@
 #![feature(test)]
 extern crate test;
 
-use std::error;
+use std::{env, error, fs};
 
 fn main() -> Result<(), Box<dyn error::Error>> {
     let filename = "input.txt";
-    let (seeds, maps) = day5a::parse_file(filename)?;
-    let seeds = create_seeds(&seeds).collect();
+    let content = fs::read_to_string(filename)?;
+    let almanac = day5a::parse(&content)?;
+    let seeds = almanac.seed_ranges()?;
 
-    let result = day5a::find_min_solution(&seeds, &maps);
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Some(options) = day5a::TraceOptions::from_args(&args)? {
+        println!("{}", options.render(&seeds, &almanac.maps)?);
+        return Ok(());
+    }
+
+    shared::configure_threads(shared::flag_value(&args, "--threads")?)?;
+    if shared::has_flag(&args, "--compare") {
+        day5a::compare_modes(&seeds, &almanac.maps, 10);
+        return Ok(());
+    }
+
+    let result = day5a::find_min_solution(&seeds, &almanac.maps);
 
     println!("Result: {}", result);
 
     Ok(())
 }
 
-fn create_seeds(values: &Vec<u64>) -> impl Iterator<Item=(u64, u64)> + '_ {
-    values.chunks(2)
-        .map(|chunk| (chunk[0], chunk[1]))
-}
-
 #[cfg(test)]
 mod tests {
     use test::Bencher;
@@ -29,18 +37,31 @@ mod tests {
     #[test]
     fn it_works() {
         let filename = "input.txt";
-        let (seeds, maps) = day5a::parse_file(filename).unwrap();
-        let seeds = create_seeds(&seeds).collect();
+        let content = std::fs::read_to_string(filename).unwrap();
+        let almanac = day5a::parse(&content).unwrap();
+        let seeds = almanac.seed_ranges().unwrap();
+
+        assert_eq!(7873084, day5a::find_min_solution(&seeds, &almanac.maps));
+    }
+
+    #[bench]
+    fn bench_sequential(b: &mut Bencher) {
+        let filename = "input.txt";
+        let content = std::fs::read_to_string(filename).unwrap();
+        let almanac = day5a::parse(&content).unwrap();
+        let seeds = almanac.seed_ranges().unwrap();
 
-        assert_eq!(7873084, day5a::find_min_solution(&seeds, &maps));
+        b.iter(|| day5a::find_min_solution_sequential(&seeds, &almanac.maps));
     }
 
+    #[cfg(feature = "parallel")]
     #[bench]
-    fn bench_solution(b: &mut Bencher) {
+    fn bench_parallel(b: &mut Bencher) {
         let filename = "input.txt";
-        let (seeds, maps) = day5a::parse_file(filename).unwrap();
-        let seeds = create_seeds(&seeds).collect();
+        let content = std::fs::read_to_string(filename).unwrap();
+        let almanac = day5a::parse(&content).unwrap();
+        let seeds = almanac.seed_ranges().unwrap();
 
-        b.iter(|| day5a::find_min_solution(&seeds, &maps));
+        b.iter(|| day5a::find_min_solution_parallel(&seeds, &almanac.maps));
     }
 }
@@ -1,6 +1,8 @@
 use std::error::Error;
+use std::fmt::{Debug, Display};
 use std::future::Future;
-use std::time::Instant;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 use tokio::fs::File;
 use tokio::io::{AsyncBufReadExt, BufReader};
@@ -19,10 +21,11 @@ pub async fn execute_solution_stream<F, Fut>(filename: &'static str, solution: F
     Ok(())
 }
 
-pub async fn execute_solution<F, Fut>(filename: &'static str, solution: F) -> Result<(), Box<dyn Error>>
+pub async fn execute_solution<F, Fut, R>(filename: &'static str, solution: F) -> Result<(), Box<dyn Error>>
     where
         F: FnOnce(String) -> Fut,
-        Fut: Future<Output=Result<u64, Box<dyn Error>>>
+        Fut: Future<Output=Result<R, Box<dyn Error>>>,
+        R: Display
 {
     let lines = std::fs::read_to_string(filename)?;
 
@@ -39,3 +42,85 @@ pub async fn read_lines(filename: &'static str) -> Result<LinesStream<BufReader<
     let reader = BufReader::new(file);
     Ok(LinesStream::new(reader.lines()))
 }
+
+/// Node id for a name in Mermaid output. The `kind` prefix keeps names like `end` from clashing with keywords.
+pub fn mermaid_id(kind: &str, name: &str) -> String {
+    format!("{}_{}", kind, name)
+}
+
+/// Whether `flag` appears anywhere in the command line arguments.
+pub fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|arg| arg == flag)
+}
+
+/// What follows `flag` in the command line arguments, `None` if the flag is missing and `Some(None)` if it is
+/// the last argument or directly followed by another flag.
+pub fn flag_argument<'a>(args: &'a [String], flag: &str) -> Option<Option<&'a str>> {
+    let idx = args.iter().position(|arg| arg == flag)?;
+    Some(args.get(idx + 1)
+        .map(String::as_str)
+        .filter(|value| !value.starts_with("--")))
+}
+
+/// The value after `flag`, `None` if the flag is missing and an error if the value is missing or does not parse.
+pub fn flag_value<T: FromStr>(args: &[String], flag: &str) -> Result<Option<T>, Box<dyn Error>>
+    where
+        T::Err: Display
+{
+    match flag_argument(args, flag) {
+        None => Ok(None),
+        Some(None) => Err(format!("Missing value after {}", flag).into()),
+        Some(Some(value)) => value.parse()
+            .map(Some)
+            .map_err(|err| format!("Could not parse {} after {}: {}", value, flag, err).into()),
+    }
+}
+
+/// Sizes the global rayon pool. Without the `parallel` feature everything runs sequentially and the count is ignored.
+pub fn configure_threads(threads: Option<usize>) -> Result<(), Box<dyn Error>> {
+    #[cfg(feature = "parallel")]
+    if let Some(threads) = threads {
+        rayon::ThreadPoolBuilder::new().num_threads(threads).build_global()?;
+    }
+    #[cfg(not(feature = "parallel"))]
+    if threads.is_some() {
+        eprintln!("Ignoring --threads, built without the parallel feature");
+    }
+    Ok(())
+}
+
+/// Runs every mode a few times and prints its result with the best and average duration.
+pub fn compare_modes<T: Debug>(runs: u32, modes: &[(&str, &dyn Fn() -> T)]) {
+    for (name, mode) in modes {
+        let mut durations = Vec::new();
+        let mut result = None;
+        for _ in 0..runs.max(1) {
+            let start = Instant::now();
+            result = Some(mode());
+            durations.push(start.elapsed());
+        }
+        let best = durations.iter().min().expect("Could not find duration");
+        let average = durations.iter().sum::<Duration>() / durations.len() as u32;
+        println!("{}: {:?} in {:?} (average {:?})", name, result.expect("Could not find result"), best, average);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flags() {
+        let args: Vec<String> = ["--json", "--threads", "4", "--trace", "--seed", "x"].map(String::from).to_vec();
+
+        assert!(has_flag(&args, "--json"));
+        assert!(!has_flag(&args, "--compare"));
+        assert_eq!(flag_argument(&args, "--threads"), Some(Some("4")));
+        assert_eq!(flag_argument(&args, "--trace"), Some(None));
+        assert_eq!(flag_argument(&args, "--part"), None);
+        assert_eq!(flag_value::<usize>(&args, "--threads").unwrap(), Some(4));
+        assert_eq!(flag_value::<usize>(&args, "--part").unwrap(), None);
+        assert_eq!(flag_value::<usize>(&args, "--trace").unwrap_err().to_string(), "Missing value after --trace");
+        assert_eq!(flag_value::<usize>(&args, "--seed").unwrap_err().to_string(), "Could not parse x after --seed: invalid digit found in string");
+    }
+}